@@ -0,0 +1,231 @@
+//! Measures the gas burnt by moves of the contract in nearcore's VM with the
+//! mainnet runtime config, so that different builds of the contract can be
+//! compared on the same games:
+//!
+//! ```console
+//! cargo run --release -- bfs=old.wasm dsu=new.wasm
+//! ```
+//!
+//! Every build plays the same games on 11x11, 15x15 and 19x19 boards: one in
+//! which the first player builds a straight chain down the middle column,
+//! and ten games of random moves played until somebody wins. The table lists
+//! the gas of the `make_move_internal` calls, which apply the moves and check
+//! the winner, as the average, the maximum and the average of winning moves.
+use std::rc::Rc;
+use std::sync::Arc;
+
+use near_parameters::vm::VMKind;
+use near_parameters::{RuntimeConfig, RuntimeConfigStore};
+use near_primitives_core::account::AccountContract;
+use near_primitives_core::code::ContractCode;
+use near_primitives_core::types::{Balance, Gas};
+use near_vm_runner::internal::VMKindExt;
+use near_vm_runner::logic::mocks::mock_external::MockedExternal;
+use near_vm_runner::logic::VMContext;
+use near_vm_runner::MockContractRuntimeCache;
+use serde_json::{json, Value};
+
+const CONTRACT: &str = "hex.near";
+const FIRST_PLAYER: &str = "alice.near";
+const SECOND_PLAYER: &str = "bob.near";
+const ONE_NEAR: u128 = 10u128.pow(24);
+const SIZES: [usize; 3] = [11, 15, 19];
+const RANDOM_GAMES: u64 = 10;
+
+/// A contract deployed on a fresh account.
+struct Runner {
+    ext: MockedExternal,
+    config: Arc<RuntimeConfig>,
+    cache: MockContractRuntimeCache,
+}
+
+impl Runner {
+    fn new(code: &[u8], cache: &MockContractRuntimeCache) -> Self {
+        let store = RuntimeConfigStore::new(None);
+        let mut config = (**store.get_config(u32::MAX)).clone();
+        let mut wasm_config = (*config.wasm_config).clone();
+        wasm_config.vm_kind = VMKind::Wasmtime;
+        config.wasm_config = Arc::new(wasm_config);
+        Self {
+            ext: MockedExternal::with_code(ContractCode::new(code.to_vec(), None)),
+            config: Arc::new(config),
+            cache: cache.clone(),
+        }
+    }
+
+    /// Calls the method and returns the burnt gas and the logs.
+    fn call(
+        &mut self,
+        predecessor: &str,
+        method: &str,
+        args: Value,
+        deposit: u128,
+    ) -> (u64, Vec<String>) {
+        let context = VMContext {
+            current_account_id: CONTRACT.parse().unwrap(),
+            signer_account_id: predecessor.parse().unwrap(),
+            signer_account_pk: vec![0; 33],
+            predecessor_account_id: predecessor.parse().unwrap(),
+            refund_to_account_id: predecessor.parse().unwrap(),
+            input: Rc::from(serde_json::to_vec(&args).unwrap()),
+            promise_results: Vec::new().into(),
+            block_height: 10,
+            block_timestamp: 42,
+            epoch_height: 1,
+            account_balance: Balance::from_yoctonear(1000 * ONE_NEAR),
+            account_locked_balance: Balance::ZERO,
+            storage_usage: 1_000_000,
+            account_contract: AccountContract::None,
+            attached_deposit: Balance::from_yoctonear(deposit),
+            prepaid_gas: Gas::from_teragas(300),
+            random_seed: vec![0; 32],
+            view_config: None,
+            output_data_receivers: vec![],
+        };
+        let wasm_config = self.config.wasm_config.clone();
+        let gas_counter = context.make_gas_counter(&wasm_config);
+        let outcome = VMKind::Wasmtime
+            .runtime(wasm_config)
+            .unwrap()
+            .prepare(&self.ext, Some(&self.cache), gas_counter, method)
+            .run(&mut self.ext, &context, self.config.fees.clone())
+            .expect("the VM failed");
+        if let Some(error) = outcome.aborted {
+            panic!("{} failed: {} {:?}", method, error, outcome.logs);
+        }
+        (outcome.burnt_gas.as_gas(), outcome.logs)
+    }
+}
+
+/// Linear congruential generator, so that every build plays the same games.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as usize % n
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Game {
+    Chain,
+    Random(u64),
+}
+
+impl Game {
+    /// Cells of the moves, in the order they are made, until the board is
+    /// full. The game ends earlier, once somebody wins.
+    fn moves(self, size: usize) -> Vec<(usize, usize)> {
+        match self {
+            // The second player fills the first column and never connects.
+            Game::Chain => (0..size).flat_map(|y| [(size / 2, y), (0, y)]).collect(),
+            Game::Random(seed) => {
+                let mut random = Random(seed);
+                let mut taken = vec![false; size * size];
+                let mut moves = vec![];
+                while moves.len() < size * size {
+                    let (x, y) = (random.below(size), random.below(size));
+                    if !taken[y * size + x] {
+                        taken[y * size + x] = true;
+                        moves.push((x, y));
+                    }
+                }
+                moves
+            }
+        }
+    }
+}
+
+/// Plays the game and returns the gas burnt by every move.
+fn play(code: &[u8], cache: &MockContractRuntimeCache, size: usize, game: Game) -> Vec<u64> {
+    let mut runner = Runner::new(code, cache);
+    runner.call(CONTRACT, "new", json!({}), 0);
+    // Older builds take the settings as separate arguments, newer ones as
+    // `options`. Both ignore the arguments they don't know.
+    let args = json!({
+        "first_player": FIRST_PLAYER,
+        "second_player": SECOND_PLAYER,
+        "field_size": size,
+        "options": {"field_size": size},
+    });
+    runner.call(FIRST_PLAYER, "create_game", args, ONE_NEAR);
+    runner.call(SECOND_PLAYER, "accept_game", json!({"index": 0}), 0);
+    let mut gas = vec![];
+    for (turn, (x, y)) in game.moves(size).into_iter().enumerate() {
+        let player = [FIRST_PLAYER, SECOND_PLAYER][turn % 2];
+        let args = json!({
+            "index": 0,
+            "account_id": player,
+            "move_type": "PLACE",
+            "cell": {"x": x, "y": y},
+        });
+        let (burnt, logs) = runner.call(CONTRACT, "make_move_internal", args, 0);
+        gas.push(burnt);
+        if logs.iter().any(|log| log.contains("game_finished")) {
+            return gas;
+        }
+    }
+    panic!("the game didn't finish");
+}
+
+fn tgas(gas: f64) -> String {
+    format!("{:.2}", gas / 1e12)
+}
+
+fn main() {
+    let builds: Vec<(String, Vec<u8>)> = std::env::args()
+        .skip(1)
+        .map(|arg| {
+            let (label, path) = arg.split_once('=').expect("expected label=path.wasm");
+            let code = std::fs::read(path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+            (label.to_string(), code)
+        })
+        .collect();
+    assert!(!builds.is_empty(), "usage: gas-bench label=path.wasm...");
+    let cache = MockContractRuntimeCache::default();
+
+    print!("| Board | Game | Moves |");
+    for (label, _) in &builds {
+        print!(" {}, Tgas (avg / max / winning move) |", label);
+    }
+    println!();
+    println!("|---|---|---|{}", "---|".repeat(builds.len()));
+    for size in SIZES {
+        let sets = [
+            ("chain", vec![Game::Chain]),
+            ("random", (1..=RANDOM_GAMES).map(Game::Random).collect()),
+        ];
+        for (name, games) in sets {
+            let mut row = String::new();
+            let mut moves = None;
+            for (_, code) in &builds {
+                let played: Vec<Vec<u64>> = games
+                    .iter()
+                    .map(|&game| play(code, &cache, size, game))
+                    .collect();
+                let all: Vec<u64> = played.iter().flatten().copied().collect();
+                let count = all.len();
+                assert!(
+                    moves.replace(count).is_none_or(|moves| moves == count),
+                    "the builds played different games"
+                );
+                let average = all.iter().sum::<u64>() as f64 / count as f64;
+                let max = *all.iter().max().unwrap() as f64;
+                let winning = played.iter().map(|gas| *gas.last().unwrap()).sum::<u64>() as f64
+                    / played.len() as f64;
+                row += &format!(" {} / {} / {} |", tgas(average), tgas(max), tgas(winning));
+            }
+            println!(
+                "| {0}x{0} | {1} | {2} |{3}",
+                size,
+                name,
+                moves.unwrap(),
+                row
+            );
+        }
+    }
+}
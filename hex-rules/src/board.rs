@@ -0,0 +1,409 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::cell::Cell;
+use crate::error::RulesError;
+use crate::topology::Topology;
+use crate::MAX_FIELD_SIZE;
+
+/// Value of a cell that neither player may occupy, see "Hex with holes".
+pub const BLOCKED: u8 = 3;
+
+/// Board with every cell packed into 2 bits: 0 is empty, 1 is red and 2 is
+/// blue. Only the cells of the topology are packed, row by row, so a Game of
+/// Y board takes about half of the square one. The field is serialized in
+/// base64.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "borsh", derive(BorshDeserialize, BorshSerialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "BoardRepr", try_from = "BoardRepr"))]
+pub struct Board {
+    pub width: usize,
+    pub height: usize,
+    pub field: Vec<u8>,
+    pub topology: Topology,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct BoardRepr {
+    width: usize,
+    height: usize,
+    field: String,
+    #[serde(default)]
+    topology: Topology,
+}
+
+#[cfg(feature = "serde")]
+impl From<Board> for BoardRepr {
+    fn from(board: Board) -> Self {
+        Self {
+            width: board.width,
+            height: board.height,
+            field: base64::encode(&board.field),
+            topology: board.topology,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl core::convert::TryFrom<BoardRepr> for Board {
+    type Error = String;
+
+    fn try_from(repr: BoardRepr) -> Result<Self, Self::Error> {
+        let mut board = Board::with_topology(repr.topology, repr.width, repr.height)
+            .map_err(|error| alloc::format!("{}", error))?;
+        let field = base64::decode(&repr.field).map_err(|_| String::from("Invalid field."))?;
+        if field.len() != board.field.len() {
+            return Err(String::from("Invalid field length."));
+        }
+        board.field = field;
+        Ok(board)
+    }
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize) -> Result<Self, RulesError> {
+        Self::with_topology(Topology::Hex, width, height)
+    }
+
+    /// Boards of the Game of Y have to be square, `width` is the length of
+    /// the sides of the triangle.
+    pub fn with_topology(
+        topology: Topology,
+        width: usize,
+        height: usize,
+    ) -> Result<Self, RulesError> {
+        if width == 0 || height == 0 || width > MAX_FIELD_SIZE || height > MAX_FIELD_SIZE {
+            return Err(RulesError::InvalidSize);
+        }
+        if topology == Topology::Y && width != height {
+            return Err(RulesError::InvalidSize);
+        }
+        let mut board = Board {
+            width,
+            height,
+            field: Vec::new(),
+            topology,
+        };
+        board.field = vec![0u8; board.cell_count().div_ceil(4)];
+        Ok(board)
+    }
+
+    pub fn cell_count(&self) -> usize {
+        match self.topology {
+            Topology::Hex => self.width * self.height,
+            Topology::Y => self.width * (self.width + 1) / 2,
+        }
+    }
+
+    /// Position of the cell in the packed field, which is also its node in
+    /// `Position::connections`.
+    pub fn index(&self, cell: &Cell) -> usize {
+        match self.topology {
+            Topology::Hex => self.width * cell.y + cell.x,
+            Topology::Y => self.row_offset(cell.y) + cell.x,
+        }
+    }
+
+    /// Index of the first cell of the row on a Game of Y board, whose rows
+    /// get one cell shorter each.
+    fn row_offset(&self, y: usize) -> usize {
+        y * self.width - y * y.saturating_sub(1) / 2
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.width == self.height
+    }
+
+    pub fn contains(&self, cell: &Cell) -> bool {
+        let in_rectangle = cell.x < self.width && cell.y < self.height;
+        match self.topology {
+            Topology::Hex => in_rectangle,
+            Topology::Y => in_rectangle && cell.x + cell.y < self.width,
+        }
+    }
+
+    /// Adjacent cells that belong to the board.
+    pub fn neighbours(&self, cell: &Cell) -> Vec<Cell> {
+        let mut neighbours = cell.get_neighbours(self.width, self.height);
+        neighbours.retain(|cell| self.contains(cell));
+        neighbours
+    }
+
+    /// Borders of the color the cell lies on, numbered from 0 to
+    /// `Topology::border_count`. In the Game of Y both colors share the top
+    /// (0), the left (1) and the diagonal (2) sides.
+    pub fn borders(&self, cell: &Cell, color: u8) -> Vec<usize> {
+        let on_borders = match (self.topology, color) {
+            (Topology::Hex, 1) => vec![cell.y == 0, cell.y + 1 == self.height],
+            (Topology::Hex, _) => vec![cell.x == 0, cell.x + 1 == self.width],
+            (Topology::Y, _) => vec![cell.y == 0, cell.x == 0, cell.x + cell.y + 1 == self.width],
+        };
+        (0..on_borders.len()).filter(|&i| on_borders[i]).collect()
+    }
+
+    fn get_byte_and_bit(&self, cell: &Cell) -> (u8, usize, usize) {
+        let index = self.index(cell) * 2;
+        let byte_index = index / 8;
+        let byte: u8 = self.field[byte_index];
+        let bit_index = index & 7;
+        (byte, byte_index, bit_index)
+    }
+
+    /// Panics if the cell is out of the board, check it with `contains` first.
+    pub fn get_cell(&self, cell: &Cell) -> u8 {
+        assert!(self.contains(cell), "Cell is out of bounds.");
+        let (byte, _, bit_index) = self.get_byte_and_bit(cell);
+        (byte >> bit_index) & 3
+    }
+
+    /// Panics if the cell is out of the board or the value isn't a color or
+    /// `BLOCKED`.
+    pub fn set_cell(&mut self, cell: &Cell, value: u8) {
+        assert!(self.contains(cell), "Cell is out of bounds.");
+        assert!(value <= BLOCKED, "Incorrect cell value.");
+        let (byte, byte_index, bit_index) = self.get_byte_and_bit(cell);
+        let bits = (byte >> bit_index) & 3;
+        let new_byte = byte ^ (bits << bit_index) ^ (value << bit_index);
+        self.field[byte_index] = new_byte;
+    }
+
+    pub fn get_coords(&self, bit_number: usize) -> Cell {
+        let index = bit_number / 2;
+        match self.topology {
+            Topology::Hex => Cell::new(index % self.width, index / self.width),
+            Topology::Y => {
+                let y = (1..self.height)
+                    .take_while(|&y| self.row_offset(y) <= index)
+                    .last()
+                    .unwrap_or(0);
+                Cell::new(index - self.row_offset(y), y)
+            }
+        }
+    }
+
+    /// All cells of the board, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        (0..self.height)
+            .flat_map(move |y| (0..self.width).map(move |x| Cell::new(x, y)))
+            .filter(move |cell| self.contains(cell))
+    }
+
+    pub fn get_board_as_strings(&self) -> Vec<String> {
+        let mut vector = Vec::new();
+
+        for i in 0..self.height {
+            let mut result: String = (0..i).map(|_| ' ').collect();
+            let row: Vec<String> = (0..self.width)
+                .map(|j| Cell::new(j, i))
+                .filter(|cell| self.contains(cell))
+                .map(|cell| String::from(cell_symbol(self.get_cell(&cell))))
+                .collect();
+            result.push_str(&row.join(" "));
+            vector.push(result);
+        }
+
+        vector
+    }
+}
+
+pub fn cell_symbol(value: u8) -> char {
+    match value {
+        0 => '.',
+        1 => 'R',
+        2 => 'B',
+        BLOCKED => '#',
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod board_tests {
+    use alloc::vec::Vec;
+
+    use crate::cell::Cell;
+    use crate::error::RulesError;
+
+    use super::{Board, Topology, BLOCKED};
+
+    #[test]
+    fn test_board_is_too_big() {
+        assert_eq!(Board::new(27, 27), Err(RulesError::InvalidSize));
+        assert_eq!(Board::new(5, 0), Err(RulesError::InvalidSize));
+    }
+    #[test]
+    fn test_get_byte_and_bit() {
+        let mut test_board = Board::new(11, 11).unwrap();
+        assert_eq!((0, 3, 6), test_board.get_byte_and_bit(&Cell::new(4, 1)));
+        assert_eq!((0, 0, 0), test_board.get_byte_and_bit(&Cell::new(0, 0)));
+        assert_eq!((0, 5, 0), test_board.get_byte_and_bit(&Cell::new(9, 1)));
+
+        let mut test_vec = vec![0u8; 7];
+        test_vec[0] = 255;
+        test_vec[1] = 19;
+        test_vec[2] = 7;
+        test_vec[3] = 113;
+        test_board = Board {
+            width: 5,
+            height: 5,
+            field: test_vec,
+            topology: Topology::Hex,
+        };
+
+        assert_eq!((255, 0, 6), test_board.get_byte_and_bit(&Cell::new(3, 0)));
+        assert_eq!((7, 2, 6), test_board.get_byte_and_bit(&Cell::new(1, 2)));
+        assert_eq!((19, 1, 0), test_board.get_byte_and_bit(&Cell::new(4, 0)));
+        assert_eq!((0, 6, 0), test_board.get_byte_and_bit(&Cell::new(4, 4)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get_cell_out_of_bounds() {
+        Board::new(11, 11).unwrap().get_cell(&Cell::new(5, 11));
+    }
+
+    #[test]
+    fn test_get_cell() {
+        let mut test_vec = vec![0u8; 7];
+        test_vec[0] = 255;
+        test_vec[1] = 32;
+        test_vec[2] = 7;
+        test_vec[3] = 113;
+        let test_board = Board {
+            width: 5,
+            height: 5,
+            field: test_vec,
+            topology: Topology::Hex,
+        };
+
+        assert_eq!(3, test_board.get_cell(&Cell::new(3, 0)));
+        assert_eq!(1, test_board.get_cell(&Cell::new(4, 1)));
+        assert_eq!(3, test_board.get_cell(&Cell::new(4, 2)));
+        assert_eq!(2, test_board.get_cell(&Cell::new(1, 1)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_cell_out_of_bounds() {
+        Board::new(5, 5).unwrap().set_cell(&Cell::new(6, 3), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_cell_too_big_value() {
+        Board::new(4, 4).unwrap().set_cell(&Cell::new(1, 1), 4);
+    }
+
+    #[test]
+    fn test_set_sell() {
+        let mut test_board = Board::new(11, 11).unwrap();
+        let test_cell = Cell::new(2, 5);
+
+        test_board.set_cell(&test_cell, 1);
+        assert_eq!(1, test_board.get_cell(&test_cell));
+
+        test_board.set_cell(&test_cell, 0);
+        assert_eq!(0, test_board.get_cell(&test_cell));
+
+        test_board.set_cell(&test_cell, 2);
+        assert_eq!(2, test_board.get_cell(&test_cell));
+
+        test_board.set_cell(&test_cell, BLOCKED);
+        assert_eq!(BLOCKED, test_board.get_cell(&test_cell));
+        test_board.set_cell(&test_cell, 2);
+
+        test_board.set_cell(&Cell::new(10, 10), 1);
+        test_board.set_cell(&Cell::new(3, 5), 1);
+
+        assert_eq!(2, test_board.get_cell(&Cell::new(2, 5)));
+        assert_eq!(1, test_board.get_cell(&Cell::new(10, 10)));
+        assert_eq!(1, test_board.get_cell(&Cell::new(3, 5)));
+    }
+
+    #[test]
+    fn test_get_coords() {
+        let mut test_board = Board::new(5, 5).unwrap();
+        assert_eq!(Cell::new(0, 1), test_board.get_coords(10));
+        assert_eq!(Cell::new(0, 0), test_board.get_coords(0));
+        assert_eq!(Cell::new(0, 0), test_board.get_coords(1));
+        assert_eq!(Cell::new(4, 4), test_board.get_coords(48));
+
+        test_board = Board::new(15, 15).unwrap();
+        let mut test_cell = Cell::new(11, 7);
+        let (_, mut byte, mut bit) = test_board.get_byte_and_bit(&test_cell);
+        assert_eq!(test_cell, test_board.get_coords(byte * 8 + bit));
+
+        test_cell = Cell::new(0, 14);
+        (_, byte, bit) = test_board.get_byte_and_bit(&test_cell);
+        assert_eq!(test_cell, test_board.get_coords(byte * 8 + bit));
+
+        test_cell = Cell::new(10, 2);
+        (_, byte, bit) = test_board.get_byte_and_bit(&test_cell);
+        assert_eq!(test_cell, test_board.get_coords(byte * 8 + bit));
+    }
+
+    #[test]
+    fn test_rectangular_board() {
+        let mut test_board = Board::new(3, 5).unwrap();
+        test_board.set_cell(&Cell::new(2, 4), 1);
+        test_board.set_cell(&Cell::new(2, 0), 2);
+        assert_eq!(1, test_board.get_cell(&Cell::new(2, 4)));
+        assert_eq!(Cell::new(2, 4), test_board.get_coords(28));
+        assert_eq!(
+            test_board.get_board_as_strings(),
+            vec![". . B", " . . .", "  . . .", "   . . .", "    . . R"]
+        );
+        assert!(!test_board.contains(&Cell::new(3, 0)));
+    }
+
+    #[test]
+    fn test_y_board() {
+        assert!(Board::with_topology(Topology::Y, 4, 3).is_err());
+        let mut test_board = Board::with_topology(Topology::Y, 4, 4).unwrap();
+        assert_eq!(test_board.cell_count(), 10);
+        assert_eq!(test_board.field.len(), 3);
+        assert!(test_board.contains(&Cell::new(3, 0)));
+        assert!(!test_board.contains(&Cell::new(3, 1)));
+        assert!(!test_board.contains(&Cell::new(1, 3)));
+        assert_eq!(test_board.index(&Cell::new(0, 1)), 4);
+        assert_eq!(test_board.index(&Cell::new(0, 3)), 9);
+        for cell in test_board.cells().collect::<Vec<_>>() {
+            assert_eq!(test_board.get_coords(test_board.index(&cell) * 2), cell);
+        }
+
+        test_board.set_cell(&Cell::new(0, 3), 1);
+        test_board.set_cell(&Cell::new(3, 0), 2);
+        assert_eq!(1, test_board.get_cell(&Cell::new(0, 3)));
+        assert_eq!(
+            test_board.get_board_as_strings(),
+            vec![". . . B", " . . .", "  . .", "   R"]
+        );
+        assert_eq!(test_board.borders(&Cell::new(0, 3), 1), vec![1, 2]);
+        assert_eq!(test_board.borders(&Cell::new(1, 1), 2), Vec::<usize>::new());
+        assert_eq!(
+            test_board.neighbours(&Cell::new(2, 1)),
+            vec![
+                Cell::new(1, 1),
+                Cell::new(2, 0),
+                Cell::new(3, 0),
+                Cell::new(1, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cells() {
+        let test_board = Board::new(2, 3).unwrap();
+        let cells: Vec<Cell> = test_board.cells().collect();
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells[1], Cell::new(1, 0));
+        assert_eq!(cells[5], Cell::new(1, 2));
+    }
+}
@@ -0,0 +1,309 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Cells are serialized as `{"x": .., "y": ..}`, but can also be passed in
+/// the text notation, e.g. `"c3"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "borsh", derive(BorshDeserialize, BorshSerialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "CellRepr"))]
+pub struct Cell {
+    pub x: usize,
+    pub y: usize,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CellRepr {
+    Coords { x: usize, y: usize },
+    Notation(String),
+}
+
+#[cfg(feature = "serde")]
+impl core::convert::TryFrom<CellRepr> for Cell {
+    type Error = String;
+
+    fn try_from(repr: CellRepr) -> Result<Self, Self::Error> {
+        match repr {
+            CellRepr::Coords { x, y } => Ok(Cell::new(x, y)),
+            CellRepr::Notation(notation) => notation.parse(),
+        }
+    }
+}
+
+/// Letter of a column or a Little Golem row, if the index isn't past `z`,
+/// i.e. off any board up to `MAX_FIELD_SIZE`.
+fn letter(index: usize) -> Option<char> {
+    (index < crate::MAX_FIELD_SIZE).then(|| (b'a' + index as u8) as char)
+}
+
+/// Formats the cell in the standard notation: the column (`x`) as a letter
+/// followed by the row (`y`) as a number starting from 1, e.g. `c3`. Columns
+/// past `z` have no letter, so such cells are shown as coordinates, e.g.
+/// `(26,0)`.
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match letter(self.x) {
+            Some(column) => write!(f, "{}{}", column, self.y + 1),
+            None => write!(f, "({},{})", self.x, self.y),
+        }
+    }
+}
+
+/// Parses both the standard notation (`c3`) and the Little Golem one, where
+/// the row is a letter as well (`cc`).
+impl FromStr for Cell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let notation = s.trim().to_ascii_lowercase();
+        let error = || format!("Invalid cell {}.", s);
+        let mut chars = notation.chars();
+        let x = match chars.next() {
+            Some(column @ 'a'..='z') => column as usize - 'a' as usize,
+            _ => return Err(error()),
+        };
+        let row = chars.as_str();
+        let y = match (row.len(), row.chars().next()) {
+            (1, Some(row @ 'a'..='z')) => row as usize - 'a' as usize,
+            _ if row.chars().all(|c| c.is_ascii_digit()) => match row.parse::<usize>() {
+                Ok(row) if row > 0 => row - 1,
+                _ => return Err(error()),
+            },
+            _ => return Err(error()),
+        };
+        Ok(Cell::new(x, y))
+    }
+}
+
+impl Cell {
+    pub fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+
+    /// Lists the adjacent cells on a board of the given size.
+    ///
+    /// Panics if the cell itself is out of the board.
+    pub fn get_neighbours(&self, width: usize, height: usize) -> Vec<Cell> {
+        assert!(self.x < width && self.y < height, "Cell is out of bounds.");
+        let mut neighbours: Vec<Cell> = Vec::new();
+        let (x, y) = (self.x, self.y);
+        if self.x > 0 {
+            neighbours.push(Cell::new(x - 1, y));
+        }
+        if self.y > 0 {
+            neighbours.push(Cell::new(x, y - 1));
+        }
+        if self.x + 1 < width && self.y > 0 {
+            neighbours.push(Cell::new(x + 1, y - 1));
+        }
+        if self.x + 1 < width {
+            neighbours.push(Cell::new(x + 1, y));
+        }
+        if self.y + 1 < height {
+            neighbours.push(Cell::new(x, y + 1));
+        }
+        if self.x > 0 && self.y + 1 < height {
+            neighbours.push(Cell::new(x - 1, y + 1));
+        }
+        neighbours
+    }
+
+    /// Formats the cell in the Little Golem notation, e.g. `cc` for `c3`,
+    /// or in the standard one if the row is past `z`.
+    pub fn to_little_golem(&self) -> String {
+        match (letter(self.x), letter(self.y)) {
+            (Some(column), Some(row)) => format!("{}{}", column, row),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Mirrors the cell in the long diagonal. This only maps square boards
+    /// onto themselves, so on other boards the swap exchanges sides instead.
+    pub fn symm(&self) -> Self {
+        Self {
+            x: self.y,
+            y: self.x,
+        }
+    }
+}
+
+#[cfg(test)]
+mod cell_tests {
+    use super::Cell;
+
+    #[test]
+    fn test_cell_neighbours_single() {
+        let test_cell = Cell::new(0, 0);
+        let neighbours = test_cell.get_neighbours(1, 1);
+        assert!(neighbours.is_empty());
+    }
+
+    #[test]
+    fn test_cell_neighbours_center() {
+        let test_cell = Cell::new(1, 1);
+        let neighbours = test_cell.get_neighbours(3, 3);
+        assert_eq!(
+            neighbours,
+            vec![
+                Cell::new(0, 1),
+                Cell::new(1, 0),
+                Cell::new(2, 0),
+                Cell::new(2, 1),
+                Cell::new(1, 2),
+                Cell::new(0, 2)
+            ]
+        );
+    }
+    #[test]
+    fn test_cell_neighbours_right_bottom_corner() {
+        let test_cell = Cell::new(1, 1);
+        let neighbours = test_cell.get_neighbours(2, 2);
+        assert_eq!(neighbours, vec![Cell::new(0, 1), Cell::new(1, 0)]);
+    }
+    #[test]
+    fn test_cell_neighbours_left_upper_corner() {
+        let test_cell = Cell::new(0, 0);
+        let neighbours = test_cell.get_neighbours(4, 4);
+        assert_eq!(neighbours, vec![Cell::new(1, 0), Cell::new(0, 1)]);
+    }
+    #[test]
+    fn test_cell_neighbours_left_bottom_corner() {
+        let test_cell = Cell::new(0, 2);
+        let neighbours = test_cell.get_neighbours(3, 3);
+        assert_eq!(
+            neighbours,
+            vec![Cell::new(0, 1), Cell::new(1, 1), Cell::new(1, 2)]
+        );
+    }
+    #[test]
+    fn test_cell_neighbours_right_upper_corner() {
+        let test_cell = Cell::new(4, 0);
+        let neighbours = test_cell.get_neighbours(5, 5);
+        assert_eq!(
+            neighbours,
+            vec![Cell::new(3, 0), Cell::new(4, 1), Cell::new(3, 1)]
+        );
+    }
+
+    #[test]
+    fn test_cell_neighbours_left_border() {
+        let test_cell = Cell::new(0, 1);
+        let neighbours = test_cell.get_neighbours(3, 3);
+        assert_eq!(
+            neighbours,
+            vec![
+                Cell::new(0, 0),
+                Cell::new(1, 0),
+                Cell::new(1, 1),
+                Cell::new(0, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cell_neighbours_upper_border() {
+        let test_cell = Cell::new(2, 0);
+        let neighbours = test_cell.get_neighbours(4, 4);
+        assert_eq!(
+            neighbours,
+            vec![
+                Cell::new(1, 0),
+                Cell::new(3, 0),
+                Cell::new(2, 1),
+                Cell::new(1, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cell_neighbours_right_border() {
+        let test_cell = Cell::new(4, 3);
+        let neighbours = test_cell.get_neighbours(5, 5);
+        assert_eq!(
+            neighbours,
+            vec![
+                Cell::new(3, 3),
+                Cell::new(4, 2),
+                Cell::new(4, 4),
+                Cell::new(3, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cell_neighbours_bottom_border() {
+        let test_cell = Cell::new(2, 5);
+        let neighbours = test_cell.get_neighbours(6, 6);
+        assert_eq!(
+            neighbours,
+            vec![
+                Cell::new(1, 5),
+                Cell::new(2, 4),
+                Cell::new(3, 4),
+                Cell::new(3, 5)
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_cell_of_bounds() {
+        Cell::new(3, 4).get_neighbours(4, 4);
+    }
+
+    #[test]
+    fn test_cell_notation() {
+        assert_eq!(Cell::new(2, 2).to_string(), "c3");
+        assert_eq!(Cell::new(0, 18).to_string(), "a19");
+        assert_eq!(Cell::new(18, 0).to_little_golem(), "sa");
+
+        assert_eq!("c3".parse::<Cell>().unwrap(), Cell::new(2, 2));
+        assert_eq!(" S19 ".parse::<Cell>().unwrap(), Cell::new(18, 18));
+        assert_eq!("cc".parse::<Cell>().unwrap(), Cell::new(2, 2));
+        assert_eq!("sa".parse::<Cell>().unwrap(), Cell::new(18, 0));
+
+        for notation in ["", "c", "3c", "c0", "c-1", "c+1", "abc", "c3a"] {
+            assert!(notation.parse::<Cell>().is_err(), "{}", notation);
+        }
+    }
+
+    #[test]
+    fn test_cell_notation_out_of_range() {
+        assert_eq!(Cell::new(26, 0).to_string(), "(26,0)");
+        assert_eq!(Cell::new(0, 26).to_string(), "a27");
+        assert_eq!(Cell::new(0, 26).to_little_golem(), "a27");
+        assert_eq!(Cell::new(26, 26).to_little_golem(), "(26,26)");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_cell_deserialize() {
+        let cell: Cell = serde_json::from_str(r#"{"x": 1, "y": 4}"#).unwrap();
+        assert_eq!(cell, Cell::new(1, 4));
+        let cell: Cell = serde_json::from_str(r#""b5""#).unwrap();
+        assert_eq!(cell, Cell::new(1, 4));
+        assert!(serde_json::from_str::<Cell>(r#""5b""#).is_err());
+        assert_eq!(serde_json::to_string(&cell).unwrap(), r#"{"x":1,"y":4}"#);
+    }
+
+    #[test]
+    fn test_cell_neighbours_rectangular() {
+        let test_cell = Cell::new(3, 0);
+        let neighbours = test_cell.get_neighbours(4, 2);
+        assert_eq!(
+            neighbours,
+            vec![Cell::new(2, 0), Cell::new(3, 1), Cell::new(2, 1)]
+        );
+        assert_eq!(Cell::new(1, 4).get_neighbours(2, 5).len(), 2);
+    }
+}
@@ -0,0 +1,63 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Disjoint-set forest with path halving. It doesn't keep ranks, which
+/// saves a byte per node in the storage of every game.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "borsh", derive(BorshDeserialize, BorshSerialize))]
+pub struct DisjointSet {
+    parent: Vec<u16>,
+}
+
+impl DisjointSet {
+    pub fn new(size: usize) -> Self {
+        Self {
+            parent: (0..size as u16).collect(),
+        }
+    }
+
+    pub fn find(&mut self, node: usize) -> usize {
+        let mut node = node;
+        while self.parent[node] as usize != node {
+            let grandparent = self.parent[self.parent[node] as usize];
+            self.parent[node] = grandparent;
+            node = grandparent as usize;
+        }
+        node
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a] = b as u16;
+        }
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+}
+
+#[cfg(test)]
+mod dsu_tests {
+    use super::DisjointSet;
+
+    #[test]
+    fn test_union_find() {
+        let mut dsu = DisjointSet::new(6);
+        assert!(!dsu.connected(0, 1));
+
+        dsu.union(0, 1);
+        dsu.union(2, 3);
+        assert!(dsu.connected(1, 0));
+        assert!(dsu.connected(2, 3));
+        assert!(!dsu.connected(1, 2));
+
+        dsu.union(3, 1);
+        assert!(dsu.connected(0, 2));
+        assert!(!dsu.connected(0, 4));
+        assert_eq!(dsu.find(5), 5);
+    }
+}
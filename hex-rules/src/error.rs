@@ -0,0 +1,35 @@
+use core::fmt;
+
+/// Reasons for the rules to reject a board or a move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RulesError {
+    InvalidSize,
+    OutOfBounds,
+    CellFilled,
+    CellBlocked,
+    SwapNotAllowed,
+    SwapDisabled,
+    GameFinished,
+    NothingToUndo,
+}
+
+impl RulesError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            RulesError::InvalidSize => "The size of the field must be between 1 and 26.",
+            RulesError::OutOfBounds => "Cell is out of bounds.",
+            RulesError::CellFilled => "Cell is already filled.",
+            RulesError::CellBlocked => "Cell is blocked.",
+            RulesError::SwapNotAllowed => "You can apply swap rule only on the second turn",
+            RulesError::SwapDisabled => "Swap rule is disabled in this game.",
+            RulesError::GameFinished => "Game is already finished!",
+            RulesError::NothingToUndo => "There is no move to take back.",
+        }
+    }
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
@@ -0,0 +1,25 @@
+//! Rules of Hex shared by the contract and off-chain tools. The crate doesn't
+//! depend on the NEAR runtime and only needs `alloc`. Enable the `borsh` and
+//! `serde` features to store and serialize the types the way the contract does,
+//! and `std` to let them use the standard library.
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod board;
+mod cell;
+mod dsu;
+mod error;
+mod position;
+mod swap_rule;
+mod topology;
+
+pub use board::{cell_symbol, Board, BLOCKED};
+pub use cell::Cell;
+pub use dsu::DisjointSet;
+pub use error::RulesError;
+pub use position::{player_color, Position};
+pub use swap_rule::SwapRule;
+pub use topology::Topology;
+
+pub const MAX_FIELD_SIZE: usize = 26;
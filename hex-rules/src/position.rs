@@ -0,0 +1,551 @@
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::board::{Board, BLOCKED};
+use crate::cell::Cell;
+use crate::dsu::DisjointSet;
+use crate::error::RulesError;
+use crate::swap_rule::SwapRule;
+use crate::topology::Topology;
+
+/// Color of the stones placed by the player: the first player connects the
+/// top and bottom borders with red (1), the second one connects the left and
+/// right borders with blue (2), unless they swapped sides.
+pub fn player_color(is_first_player: bool, sides_swapped: bool) -> u8 {
+    if is_first_player != sides_swapped {
+        1
+    } else {
+        2
+    }
+}
+
+/// Everything the rules need to know about a game: the stones, the number of
+/// moves made, including the swap, and whether the players swapped sides.
+#[derive(Clone, Debug)]
+pub struct Position {
+    pub board: Board,
+    pub turn: usize,
+    pub sides_swapped: bool,
+    /// Groups of connected stones. Besides the cells, it contains a virtual
+    /// node for each border: first the ones of red, then the ones of blue.
+    pub connections: DisjointSet,
+}
+
+impl Position {
+    pub fn new(width: usize, height: usize) -> Result<Self, RulesError> {
+        Ok(Self::from_board(Board::new(width, height)?, 0, false))
+    }
+
+    /// Sets up the position from the stones on the board, e.g. one sent by a
+    /// client or loaded from a record.
+    pub fn from_board(board: Board, turn: usize, sides_swapped: bool) -> Self {
+        let mut position = Self {
+            board,
+            turn,
+            sides_swapped,
+            connections: DisjointSet::default(),
+        };
+        position.rebuild_connections();
+        position
+    }
+
+    pub fn color_to_move(&self) -> u8 {
+        player_color(self.turn.is_multiple_of(2), self.sides_swapped)
+    }
+
+    /// Color that connected all of its borders, if any.
+    pub fn winner(&mut self) -> Option<u8> {
+        [1, 2].iter().copied().find(|&color| {
+            let mut borders = self.border_nodes(color);
+            let first = borders.next().unwrap();
+            borders.all(|border| self.connections.connected(first, border))
+        })
+    }
+
+    /// The board is full, but neither color connected its borders, which is
+    /// only possible if some cells are blocked.
+    pub fn is_draw(&mut self) -> bool {
+        self.winner().is_none()
+            && self
+                .board
+                .cells()
+                .all(|cell| self.board.get_cell(&cell) != 0)
+    }
+
+    pub fn is_finished(&mut self) -> bool {
+        self.winner().is_some() || self.is_draw()
+    }
+
+    /// Places a stone of the color to move.
+    pub fn place(&mut self, cell: &Cell) -> Result<(), RulesError> {
+        if self.is_finished() {
+            return Err(RulesError::GameFinished);
+        }
+        if !self.board.contains(cell) {
+            return Err(RulesError::OutOfBounds);
+        }
+        match self.board.get_cell(cell) {
+            0 => {}
+            BLOCKED => return Err(RulesError::CellBlocked),
+            _ => return Err(RulesError::CellFilled),
+        }
+        self.board.set_cell(cell, self.color_to_move());
+        self.turn += 1;
+        self.process_cell(cell);
+        Ok(())
+    }
+
+    /// Applies the swap rule on the second turn and returns the cell of the
+    /// swapped stone. With `SwapRule::SwapPieces` the stone is mirrored in the
+    /// long diagonal and changes its color, with `SwapRule::SwapSides` the
+    /// players swap sides and the stone stays as it is.
+    pub fn swap(&mut self, rule: SwapRule) -> Result<Cell, RulesError> {
+        if self.is_finished() {
+            return Err(RulesError::GameFinished);
+        }
+        if rule == SwapRule::NoSwap || !rule.is_supported_by(&self.board) {
+            return Err(RulesError::SwapDisabled);
+        }
+        if self.turn != 1 {
+            return Err(RulesError::SwapNotAllowed);
+        }
+        let cell = self
+            .board
+            .cells()
+            .find(|cell| matches!(self.board.get_cell(cell), 1 | 2))
+            .ok_or(RulesError::SwapNotAllowed)?;
+        if rule == SwapRule::SwapPieces && self.board.get_cell(&cell.symm()) == BLOCKED {
+            return Err(RulesError::CellBlocked);
+        }
+        let cell = if rule == SwapRule::SwapPieces {
+            self.board.set_cell(&cell, 0);
+            self.board.set_cell(&cell.symm(), 2);
+            cell.symm()
+        } else {
+            self.sides_swapped = true;
+            cell
+        };
+        self.turn += 1;
+        self.rebuild_connections();
+        Ok(cell)
+    }
+
+    /// Reverts the last move if it placed a stone on the cell.
+    pub fn undo_place(&mut self, cell: &Cell) -> Result<(), RulesError> {
+        if self.turn == 0 || !self.board.contains(cell) || !self.is_stone(cell) {
+            return Err(RulesError::NothingToUndo);
+        }
+        self.board.set_cell(cell, 0);
+        self.turn -= 1;
+        self.rebuild_connections();
+        Ok(())
+    }
+
+    /// Reverts the swap, given the cell returned by `swap`.
+    pub fn undo_swap(&mut self, cell: &Cell) -> Result<(), RulesError> {
+        if self.turn != 2 || !self.board.contains(cell) || !self.is_stone(cell) {
+            return Err(RulesError::NothingToUndo);
+        }
+        if self.sides_swapped {
+            self.sides_swapped = false;
+        } else {
+            self.board.set_cell(cell, 0);
+            self.board.set_cell(&cell.symm(), 1);
+        }
+        self.turn -= 1;
+        self.rebuild_connections();
+        Ok(())
+    }
+
+    /// Finds the shortest chain of stones of the given color connecting its
+    /// borders, ordered from the first border to the second one. In the Game
+    /// of Y the chain is followed by the branch from it to the third side:
+    /// both start at the stone with the smallest total distance to the sides.
+    pub fn winning_path(&self, color: u8) -> Vec<Cell> {
+        let searches: Vec<_> = (0..self.board.topology.border_count())
+            .map(|border| self.distances_to_border(color, border))
+            .collect();
+        let order = &searches[0].0;
+        let cell = match self.board.topology {
+            Topology::Hex => order
+                .iter()
+                .find(|cell| self.board.borders(cell, color).contains(&1)),
+            Topology::Y => {
+                let total = |cell: &Cell| {
+                    let index = self.board.index(cell);
+                    searches
+                        .iter()
+                        .map(|(_, parent)| parent[index].as_ref().map(|(_, distance)| *distance))
+                        .sum::<Option<usize>>()
+                };
+                let best = order.iter().filter_map(total).min();
+                order
+                    .iter()
+                    .find(|cell| best.is_some() && total(cell) == best)
+            }
+        };
+        let cell = match cell {
+            Some(cell) => cell,
+            None => return Vec::new(),
+        };
+        let mut path = self.trace(&searches[0].1, cell);
+        path.reverse();
+        for (_, parent) in &searches[1..] {
+            path.extend(self.trace(parent, cell).into_iter().skip(1));
+        }
+        path
+    }
+
+    /// Runs a BFS over the stones of the color from the ones on the border.
+    /// Returns the stones in the order they were reached together with the
+    /// previous stone and the distance to the border for each of them.
+    #[allow(clippy::type_complexity)]
+    fn distances_to_border(
+        &self,
+        color: u8,
+        border: usize,
+    ) -> (Vec<Cell>, Vec<Option<(Cell, usize)>>) {
+        let mut parent: Vec<Option<(Cell, usize)>> = vec![None; self.board.cell_count()];
+        let mut order = Vec::new();
+        let mut q: VecDeque<Cell> = VecDeque::new();
+        for cell in self.board.cells() {
+            if self.board.get_cell(&cell) == color
+                && self.board.borders(&cell, color).contains(&border)
+            {
+                parent[self.board.index(&cell)] = Some((cell.clone(), 0));
+                q.push_back(cell);
+            }
+        }
+        while let Some(v) = q.pop_front() {
+            let distance = parent[self.board.index(&v)].as_ref().unwrap().1;
+            for c in self.board.neighbours(&v) {
+                if self.board.get_cell(&c) == color && parent[self.board.index(&c)].is_none() {
+                    parent[self.board.index(&c)] = Some((v.clone(), distance + 1));
+                    q.push_back(c);
+                }
+            }
+            order.push(v);
+        }
+        (order, parent)
+    }
+
+    /// Follows the BFS parents from the cell back to the border.
+    fn trace(&self, parent: &[Option<(Cell, usize)>], cell: &Cell) -> Vec<Cell> {
+        let mut path = vec![cell.clone()];
+        while let Some((previous, distance)) = &parent[self.board.index(path.last().unwrap())] {
+            if *distance == 0 {
+                break;
+            }
+            path.push(previous.clone());
+        }
+        path
+    }
+
+    /// Recomputes the groups from the board, as they can't be split when a
+    /// stone is removed.
+    fn rebuild_connections(&mut self) {
+        let border_count = self.board.topology.border_count();
+        self.connections = DisjointSet::new(self.board.cell_count() + 2 * border_count);
+        for cell in self.board.cells().collect::<Vec<_>>() {
+            self.process_cell(&cell);
+        }
+    }
+
+    fn is_stone(&self, cell: &Cell) -> bool {
+        matches!(self.board.get_cell(cell), 1 | 2)
+    }
+
+    fn border_nodes(&self, color: u8) -> Range<usize> {
+        let border_count = self.board.topology.border_count();
+        let first_border = self.board.cell_count() + border_count * (color as usize - 1);
+        first_border..first_border + border_count
+    }
+
+    /// Joins the stone with its neighbours of the same color and the borders
+    /// it touches. Empty and blocked cells don't belong to any group.
+    fn process_cell(&mut self, cell: &Cell) {
+        if !self.is_stone(cell) {
+            return;
+        }
+        let index = self.board.index(cell);
+        let color = self.board.get_cell(cell);
+        let first_border = self.border_nodes(color).start;
+        for border in self.board.borders(cell, color) {
+            self.connections.union(index, first_border + border);
+        }
+        for c in self.board.neighbours(cell) {
+            if self.board.get_cell(&c) == color {
+                self.connections.union(index, self.board.index(&c));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod position_tests {
+    use super::*;
+
+    fn index(position: &Position, cell: &Cell) -> usize {
+        position.board.index(cell)
+    }
+
+    fn connected(position: &mut Position, a: &Cell, b: &Cell) -> bool {
+        let (a, b) = (index(position, a), index(position, b));
+        position.connections.connected(a, b)
+    }
+
+    fn on_border(position: &mut Position, cell: &Cell, color: u8, border: usize) -> bool {
+        let border = position.border_nodes(color).start + border - 1;
+        let cell = index(position, cell);
+        position.connections.connected(cell, border)
+    }
+
+    #[test]
+    fn test_process_cell() {
+        let mut position = Position::new(5, 5).unwrap();
+        position.board.set_cell(&Cell::new(0, 0), 1);
+        position.board.set_cell(&Cell::new(0, 1), 1);
+        position.board.set_cell(&Cell::new(0, 2), 1);
+
+        position.board.set_cell(&Cell::new(4, 4), 1);
+        position.board.set_cell(&Cell::new(3, 4), 1);
+
+        position.board.set_cell(&Cell::new(0, 3), 1);
+        position.board.set_cell(&Cell::new(1, 2), 1);
+
+        position.board.set_cell(&Cell::new(4, 0), 2);
+
+        position.board.set_cell(&Cell::new(2, 1), 1);
+
+        position.board.set_cell(&Cell::new(3, 0), 2);
+
+        position.process_cell(&Cell::new(0, 1));
+        assert!(!on_border(&mut position, &Cell::new(0, 1), 1, 1));
+        assert!(connected(&mut position, &Cell::new(0, 1), &Cell::new(0, 2)));
+        assert!(!connected(
+            &mut position,
+            &Cell::new(0, 1),
+            &Cell::new(1, 2)
+        ));
+
+        position.process_cell(&Cell::new(0, 0));
+        assert!(on_border(&mut position, &Cell::new(0, 1), 1, 1));
+        assert!(connected(&mut position, &Cell::new(0, 0), &Cell::new(0, 1)));
+
+        for &(x, y) in [(0, 2), (0, 3), (1, 2), (2, 1)].iter() {
+            position.process_cell(&Cell::new(x, y));
+        }
+        assert!(connected(&mut position, &Cell::new(0, 0), &Cell::new(2, 1)));
+        assert!(on_border(&mut position, &Cell::new(2, 1), 1, 1));
+        assert!(!on_border(&mut position, &Cell::new(2, 1), 1, 2));
+        assert!(!position.is_finished());
+
+        position.process_cell(&Cell::new(4, 0));
+        position.process_cell(&Cell::new(3, 0));
+        assert!(connected(&mut position, &Cell::new(3, 0), &Cell::new(4, 0)));
+        assert!(on_border(&mut position, &Cell::new(3, 0), 2, 2));
+        assert!(!on_border(&mut position, &Cell::new(3, 0), 1, 1));
+
+        position.process_cell(&Cell::new(3, 4));
+        position.process_cell(&Cell::new(4, 4));
+        assert!(on_border(&mut position, &Cell::new(3, 4), 1, 2));
+        assert!(!position.is_finished());
+
+        position.board.set_cell(&Cell::new(2, 2), 1);
+        position.board.set_cell(&Cell::new(2, 3), 1);
+        position.board.set_cell(&Cell::new(3, 3), 1);
+        position.process_cell(&Cell::new(2, 2));
+        position.process_cell(&Cell::new(2, 3));
+        assert!(!position.is_finished());
+        position.process_cell(&Cell::new(3, 3));
+        assert_eq!(position.winner(), Some(1));
+    }
+
+    #[test]
+    fn test_place() {
+        let mut position = Position::new(3, 3).unwrap();
+        position.place(&Cell::new(1, 0)).unwrap();
+        assert_eq!(position.color_to_move(), 2);
+        assert_eq!(
+            position.place(&Cell::new(1, 0)),
+            Err(RulesError::CellFilled)
+        );
+        assert_eq!(
+            position.place(&Cell::new(3, 0)),
+            Err(RulesError::OutOfBounds)
+        );
+        assert_eq!(position.turn, 1);
+
+        for &(x, y) in [(0, 1), (0, 0), (1, 1), (2, 0), (2, 1)].iter() {
+            position.place(&Cell::new(x, y)).unwrap();
+        }
+        assert_eq!(position.board.get_cell(&Cell::new(2, 1)), 2);
+        assert_eq!(position.winner(), Some(2));
+        assert_eq!(
+            position.place(&Cell::new(0, 0)),
+            Err(RulesError::GameFinished)
+        );
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut position = Position::new(11, 11).unwrap();
+        assert_eq!(
+            position.swap(SwapRule::SwapPieces),
+            Err(RulesError::SwapNotAllowed)
+        );
+
+        position.place(&Cell::new(10, 7)).unwrap();
+        assert_eq!(
+            position.swap(SwapRule::NoSwap),
+            Err(RulesError::SwapDisabled)
+        );
+        assert_eq!(position.swap(SwapRule::SwapPieces), Ok(Cell::new(7, 10)));
+        assert_eq!(position.board.get_cell(&Cell::new(10, 7)), 0);
+        assert_eq!(position.board.get_cell(&Cell::new(7, 10)), 2);
+        assert_eq!(position.color_to_move(), 1);
+        assert_eq!(
+            position.swap(SwapRule::SwapPieces),
+            Err(RulesError::SwapNotAllowed)
+        );
+
+        position.undo_swap(&Cell::new(7, 10)).unwrap();
+        assert_eq!(position.board.get_cell(&Cell::new(10, 7)), 1);
+        assert_eq!(position.turn, 1);
+    }
+
+    #[test]
+    fn test_swap_sides() {
+        let mut position = Position::new(3, 2).unwrap();
+        position.place(&Cell::new(0, 0)).unwrap();
+        assert_eq!(
+            position.swap(SwapRule::SwapPieces),
+            Err(RulesError::SwapDisabled)
+        );
+        assert_eq!(position.swap(SwapRule::SwapSides), Ok(Cell::new(0, 0)));
+        assert!(position.sides_swapped);
+        assert_eq!(position.color_to_move(), 2);
+
+        position.place(&Cell::new(2, 0)).unwrap();
+        position.place(&Cell::new(0, 1)).unwrap();
+        assert_eq!(position.winner(), Some(1));
+        assert_eq!(
+            position.winning_path(1),
+            vec![Cell::new(0, 0), Cell::new(0, 1)]
+        );
+    }
+
+    #[test]
+    fn test_game_of_y() {
+        let mut board = Board::with_topology(Topology::Y, 5, 5).unwrap();
+        for &(x, y) in [(1, 0), (1, 1), (0, 2), (2, 1)].iter() {
+            board.set_cell(&Cell::new(x, y), 1);
+        }
+        board.set_cell(&Cell::new(3, 1), 2);
+        let mut position = Position::from_board(board, 4, false);
+        assert_eq!(position.winner(), None);
+        assert!(on_border(&mut position, &Cell::new(2, 1), 1, 2));
+        assert!(!on_border(&mut position, &Cell::new(2, 1), 1, 3));
+        assert_eq!(
+            position.place(&Cell::new(2, 3)),
+            Err(RulesError::OutOfBounds)
+        );
+
+        position.place(&Cell::new(2, 2)).unwrap();
+        assert_eq!(position.winner(), Some(1));
+        assert_eq!(
+            position.winning_path(1),
+            vec![
+                Cell::new(1, 0),
+                Cell::new(1, 1),
+                Cell::new(0, 2),
+                Cell::new(2, 1),
+                Cell::new(2, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blocked_cells() {
+        let mut board = Board::new(3, 3).unwrap();
+        board.set_cell(&Cell::new(0, 1), BLOCKED);
+        let mut position = Position::from_board(board, 0, false);
+        assert_eq!(
+            position.place(&Cell::new(0, 1)),
+            Err(RulesError::CellBlocked)
+        );
+        position.place(&Cell::new(1, 0)).unwrap();
+        assert_eq!(
+            position.swap(SwapRule::SwapPieces),
+            Err(RulesError::CellBlocked)
+        );
+        assert_eq!(position.swap(SwapRule::SwapSides), Ok(Cell::new(1, 0)));
+
+        let mut board = Board::new(2, 2).unwrap();
+        board.set_cell(&Cell::new(1, 0), BLOCKED);
+        board.set_cell(&Cell::new(0, 1), BLOCKED);
+        let mut position = Position::from_board(board, 0, false);
+        position.place(&Cell::new(0, 0)).unwrap();
+        assert!(!position.is_finished());
+        position.place(&Cell::new(1, 1)).unwrap();
+        assert_eq!(position.winner(), None);
+        assert!(position.is_draw());
+        assert_eq!(
+            position.undo_place(&Cell::new(0, 1)),
+            Err(RulesError::NothingToUndo)
+        );
+    }
+
+    #[test]
+    fn test_undo_place() {
+        let mut position = Position::new(2, 2).unwrap();
+        assert_eq!(
+            position.undo_place(&Cell::new(0, 0)),
+            Err(RulesError::NothingToUndo)
+        );
+        position.place(&Cell::new(0, 0)).unwrap();
+        position.place(&Cell::new(1, 0)).unwrap();
+        position.place(&Cell::new(0, 1)).unwrap();
+        assert!(position.is_finished());
+
+        position.undo_place(&Cell::new(0, 1)).unwrap();
+        assert!(!position.is_finished());
+        assert_eq!(position.turn, 2);
+        assert_eq!(position.color_to_move(), 1);
+    }
+
+    #[test]
+    fn test_winning_path() {
+        let mut board = Board::new(4, 4).unwrap();
+        for &(x, y) in [
+            (0, 1),
+            (1, 1),
+            (2, 0),
+            (3, 0),
+            (1, 2),
+            (2, 2),
+            (3, 2),
+            (0, 3),
+        ]
+        .iter()
+        {
+            board.set_cell(&Cell::new(x, y), 2);
+        }
+        board.set_cell(&Cell::new(1, 0), 1);
+        let mut position = Position::from_board(board, 9, false);
+
+        assert_eq!(position.winner(), Some(2));
+        assert_eq!(
+            position.winning_path(2),
+            vec![
+                Cell::new(0, 1),
+                Cell::new(1, 1),
+                Cell::new(2, 0),
+                Cell::new(3, 0)
+            ]
+        );
+        assert!(position.winning_path(1).is_empty());
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+
+/// How the second player may use the swap (pie) rule on their first move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "borsh", derive(BorshDeserialize, BorshSerialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SwapRule {
+    NoSwap,
+    /// The first stone is mirrored along the long diagonal and recolored, so
+    /// that the players keep their borders. Only possible on square boards.
+    SwapPieces,
+    /// The players exchange colors and the first stone stays where it is.
+    SwapSides,
+}
+
+impl SwapRule {
+    /// Swap pieces on square boards and swap sides on the other ones.
+    pub fn default_for(board: &Board) -> Self {
+        if board.is_square() {
+            SwapRule::SwapPieces
+        } else {
+            SwapRule::SwapSides
+        }
+    }
+
+    pub fn is_supported_by(&self, board: &Board) -> bool {
+        *self != SwapRule::SwapPieces || board.is_square()
+    }
+}
+
+#[cfg(test)]
+mod swap_rule_tests {
+    use super::*;
+
+    #[test]
+    fn test_default_for() {
+        let square = Board::new(5, 5).unwrap();
+        let rectangle = Board::new(5, 4).unwrap();
+        assert_eq!(SwapRule::default_for(&square), SwapRule::SwapPieces);
+        assert_eq!(SwapRule::default_for(&rectangle), SwapRule::SwapSides);
+        assert!(SwapRule::SwapPieces.is_supported_by(&square));
+        assert!(!SwapRule::SwapPieces.is_supported_by(&rectangle));
+        assert!(SwapRule::NoSwap.is_supported_by(&rectangle));
+    }
+}
@@ -0,0 +1,40 @@
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Shape of the board together with the borders the players have to connect.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "borsh", derive(BorshDeserialize, BorshSerialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Topology {
+    /// A rhombus, where red connects the top and bottom borders and blue
+    /// connects the left and right ones.
+    #[default]
+    Hex,
+    /// The Game of Y: a triangle made of the cells with `x + y < size` of a
+    /// square board, where both colors have to touch all three sides.
+    Y,
+}
+
+impl Topology {
+    /// Number of borders each color has to connect.
+    pub fn border_count(&self) -> usize {
+        match self {
+            Topology::Hex => 2,
+            Topology::Y => 3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod topology_tests {
+    use super::*;
+
+    #[test]
+    fn test_border_count() {
+        assert_eq!(Topology::default(), Topology::Hex);
+        assert_eq!(Topology::Hex.border_count(), 2);
+        assert_eq!(Topology::Y.border_count(), 3);
+    }
+}
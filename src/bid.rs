@@ -1,14 +1,16 @@
 use near_sdk::{
     borsh::{BorshDeserialize, BorshSerialize},
     json_types::Base58CryptoHash,
-    Promise, PromiseResult,
+    Promise,
 };
 
 use crate::{
+    errors::{ensure, HexError},
+    events::HexEvent,
     external::AccountView,
     game::Player,
     roketo::{get_two_streams, roketo_create_stream, roketo_get_account, stop_stream},
-    utils::{FEE, MIN_MAKE_BID_GAS},
+    utils::{promise_result_json, FEE, MIN_MAKE_BID_GAS},
     *,
 };
 
@@ -41,60 +43,70 @@ impl Bid {
 #[near_bindgen]
 impl Contract {
     #[payable]
-    pub fn make_bid(&mut self, game_id: GameIndex) -> Promise {
-        let opt_bid = self.bids.get(&game_id);
-        require!(
-            opt_bid.is_some(),
-            "There's no betting game with such index."
-        );
-        require!(
+    #[handle_result]
+    pub fn make_bid(&mut self, game_id: GameIndex) -> Result<Promise, HexError> {
+        let bid = self.bids.get(&game_id).ok_or(HexError::NotBettingGame)?;
+        ensure(
             env::prepaid_gas() >= MIN_MAKE_BID_GAS,
-            "You should attach more gas"
-        );
+            HexError::NotEnoughGas,
+        )?;
 
-        let game = self.games.get(&game_id).unwrap().game;
-        let bid = opt_bid.unwrap();
+        let game = self.games.get(&game_id).ok_or(HexError::GameNotFound)?.game;
+        ensure(game.is_started, HexError::GameNotStarted)?;
         let account_id = env::predecessor_account_id();
 
-        require!(env::attached_deposit() >= 2 * bid.bid + FEE);
+        ensure(
+            env::attached_deposit() >= 2 * bid.bid + FEE,
+            HexError::NotEnoughDeposit,
+        )?;
 
         if account_id == game.first_player && !bid.did_first_player_bet {
-            roketo_create_stream(bid.bid, game.playtime.unwrap(), account_id)
-                .then(roketo_get_account(env::current_account_id()))
-                .then(Self::ext(env::current_account_id()).resolve_player_bid(
-                    bid,
-                    game_id,
-                    Player::First,
-                ))
+            Ok(
+                roketo_create_stream(bid.bid, game.playtime.unwrap(), account_id)
+                    .then(roketo_get_account(env::current_account_id()))
+                    .then(Self::ext(env::current_account_id()).resolve_player_bid(
+                        bid,
+                        game_id,
+                        Player::First,
+                    )),
+            )
         } else if account_id == game.second_player && !bid.did_second_player_bet {
-            roketo_create_stream(bid.bid, game.playtime.unwrap(), account_id)
-                .then(roketo_get_account(env::current_account_id()))
-                .then(Self::ext(env::current_account_id()).resolve_player_bid(
-                    bid,
-                    game_id,
-                    Player::Second,
-                ))
+            Ok(
+                roketo_create_stream(bid.bid, game.playtime.unwrap(), account_id)
+                    .then(roketo_get_account(env::current_account_id()))
+                    .then(Self::ext(env::current_account_id()).resolve_player_bid(
+                        bid,
+                        game_id,
+                        Player::Second,
+                    )),
+            )
         } else {
-            require!(false, "Invalid bet");
-            unreachable!();
+            Err(HexError::InvalidBet)
         }
     }
 
     #[private]
-    pub fn resolve_player_bid(&mut self, bid: Bid, game_id: GameIndex, player: Player) {
-        require!(env::predecessor_account_id() == env::current_account_id());
-        require!(env::promise_results_count() == 1, "ERR_TOO_MANY_RESULTS");
-        let stream_id = match env::promise_result(0) {
-            PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Successful(val) => {
-                if let Ok(account) = near_sdk::serde_json::from_slice::<AccountView>(&val) {
-                    account.last_created_stream.unwrap()
-                } else {
-                    env::panic_str("ERR_WRONG_VAL_RECEIVED")
-                }
-            }
-            PromiseResult::Failed => env::panic_str("ERR_CALL_FAILED"),
-        };
+    #[handle_result]
+    pub fn resolve_player_bid(
+        &mut self,
+        bid: Bid,
+        game_id: GameIndex,
+        player: Player,
+    ) -> Result<(), HexError> {
+        ensure(
+            env::promise_results_count() == 1,
+            HexError::WrongResultsCount,
+        )?;
+        let stream_id = promise_result_json::<AccountView>(0)?
+            .last_created_stream
+            .ok_or(HexError::WrongValueReceived)?;
+        let game = self.games.get(&game_id).ok_or(HexError::GameNotFound)?.game;
+        HexEvent::BidPlaced {
+            game_id,
+            player: game.player_account(&player).clone(),
+            amount: U128(bid.bid),
+        }
+        .emit();
         let new_bid = match player {
             Player::First => Bid {
                 did_first_player_bet: true,
@@ -108,28 +120,45 @@ impl Contract {
             },
         };
         self.bids.insert(&game_id, &new_bid);
+        Ok(())
     }
 
-    pub(crate) fn player_won(&self, bid: &Bid, game: &Game, player: Player) -> Promise {
-        match player {
-            Player::First => Promise::new(game.first_player.clone()).transfer(bid.bid),
-            Player::Second => Promise::new(game.second_player.clone()).transfer(bid.bid),
+    pub(crate) fn player_won(
+        &self,
+        game_id: GameIndex,
+        bid: &Bid,
+        game: &Game,
+        player: Player,
+    ) -> Promise {
+        let receiver = game.player_account(&player).clone();
+        HexEvent::PayoutSent {
+            game_id,
+            receiver: receiver.clone(),
+            amount: U128(bid.bid),
         }
+        .emit();
+        Promise::new(receiver).transfer(bid.bid)
     }
 
-    pub(crate) fn check_stream_bids(&mut self, game_id: GameIndex) -> Option<Promise> {
-        let bid = self.bids.get(&game_id);
-        if bid.is_none() {
-            None
-        } else {
-            let unwrap_bid = bid.unwrap();
-            Some(
-                get_two_streams(
-                    unwrap_bid.stream_to_first_player,
-                    unwrap_bid.stream_to_second_player,
-                )
-                .then(Self::ext(env::current_account_id()).parse_two_promise_streams()),
-            )
+    /// Returns the bids to both players, e.g. after a draw.
+    pub(crate) fn refund_bids(&self, game_id: GameIndex, bid: &Bid, game: &Game) -> Promise {
+        for receiver in [&game.first_player, &game.second_player] {
+            HexEvent::PayoutSent {
+                game_id,
+                receiver: receiver.clone(),
+                amount: U128(bid.bid),
+            }
+            .emit();
         }
+        Promise::new(game.first_player.clone())
+            .transfer(bid.bid)
+            .then(Promise::new(game.second_player.clone()).transfer(bid.bid))
+    }
+
+    pub(crate) fn check_stream_bids(&mut self, game_id: GameIndex) -> Option<Promise> {
+        self.bids.get(&game_id).map(|bid| {
+            get_two_streams(bid.stream_to_first_player, bid.stream_to_second_player)
+                .then(Self::ext(env::current_account_id()).parse_two_promise_streams())
+        })
     }
 }
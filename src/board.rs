@@ -1,199 +1,79 @@
-use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::json_types::Base64VecU8;
-use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, require};
+use hex_rules::{cell_symbol, Board};
+use near_sdk::env;
 
-use crate::cell::Cell;
+use crate::utils::MAX_LOGGED_FIELD_SIZE;
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
-#[serde(crate = "near_sdk::serde")]
-pub struct Board {
-    pub size: usize,
-    pub field: Base64VecU8,
-}
+/// Logging of boards, which needs the NEAR runtime and so isn't a part of
+/// the rules.
+pub trait BoardLogs {
+    fn debug_logs(&self);
 
-impl Board {
-    pub fn new(size: usize) -> Self {
-        require!(size <= 19, "The size of the field must be less or equal 19");
-        let field_len = (size * size + 3) / 4;
-        Board {
-            size,
-            field: Base64VecU8::from(vec![0u8; field_len]),
-        }
-    }
+    fn is_logged_whole(&self) -> bool;
 
-    fn get_byte_and_bit(&self, cell: &Cell) -> (u8, usize, usize) {
-        let index = (self.size * cell.y + cell.x) * 2;
-        let byte_index = index / 8;
-        let byte: u8 = self.field.0[byte_index];
-        let bit_index = index & 7;
-        (byte, byte_index, bit_index)
-    }
-
-    pub fn get_cell(&self, cell: &Cell) -> u8 {
-        require!(
-            cell.x < self.size && cell.y < self.size,
-            "Cell is out of bounds."
-        );
-        let (byte, _, bit_index) = self.get_byte_and_bit(cell);
-        (byte >> bit_index) & 3
-    }
+    /// Logs the old and the new board, or only the cells that changed if the
+    /// board is too big to be logged on every move.
+    fn log_changes(&self, old_board: &Board);
+}
 
-    pub fn set_cell(&mut self, cell: &Cell, value: u8) {
-        let (byte, byte_index, bit_index) = self.get_byte_and_bit(cell);
-        let bits = (byte >> bit_index) & 3;
-        let new_byte = byte ^ (bits << bit_index) ^ (value << bit_index);
-        self.field.0[byte_index] = new_byte;
+impl BoardLogs for Board {
+    fn debug_logs(&self) {
+        self.get_board_as_strings()
+            .into_iter()
+            .for_each(|s| env::log_str(&s));
     }
 
-    pub fn get_coords(&self, bit_number: usize) -> Cell {
-        Cell::new(bit_number / 2 % self.size, bit_number / 2 / self.size)
+    fn is_logged_whole(&self) -> bool {
+        self.width <= MAX_LOGGED_FIELD_SIZE && self.height <= MAX_LOGGED_FIELD_SIZE
     }
 
-    pub fn get_board_as_strings(&self) -> Vec<String> {
-        let mut vector = Vec::new();
+    fn log_changes(&self, old_board: &Board) {
+        if self.is_logged_whole() {
+            env::log_str("Old board:");
+            old_board.debug_logs();
 
-        for i in 0..self.size {
-            let mut result: String = (0..i).into_iter().map(|_| ' ').collect();
-            for j in 0..self.size {
-                let symbol = match self.get_cell(&Cell::new(j, i)) {
-                    0 => '.',
-                    1 => 'R',
-                    2 => 'B',
-                    _ => unreachable!(),
-                };
-                result.push(symbol);
-                if j + 1 != self.size {
-                    result.push(' ');
-                }
+            env::log_str("New board:");
+            self.debug_logs();
+            return;
+        }
+        let mut changes = Vec::new();
+        for cell in self.cells() {
+            let (old, new) = (old_board.get_cell(&cell), self.get_cell(&cell));
+            if old != new {
+                changes.push(format!(
+                    "{} {} -> {}",
+                    cell,
+                    cell_symbol(old),
+                    cell_symbol(new)
+                ));
             }
-            vector.push(result);
         }
-
-        vector
-    }
-
-    pub fn debug_logs(&self) {
-        self.get_board_as_strings()
-            .into_iter()
-            .for_each(|s| env::log_str(&s));
+        env::log_str(&format!("Changed cells: {}.", changes.join(", ")));
     }
 }
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod board_tests {
-    use near_sdk::json_types::Base64VecU8;
-
-    use crate::cell::Cell;
+    use hex_rules::Cell;
+    use near_sdk::test_utils::get_logs;
 
-    use super::Board;
+    use super::*;
 
     #[test]
-    #[should_panic]
-    fn test_board_is_too_big() {
-        Board::new(20);
-    }
-
-    #[test]
-    fn test_get_byte_and_bit() {
-        let mut test_board = Board::new(11);
-        assert_eq!((0, 3, 6), test_board.get_byte_and_bit(&Cell::new(4, 1)));
-        assert_eq!((0, 0, 0), test_board.get_byte_and_bit(&Cell::new(0, 0)));
-        assert_eq!((0, 5, 0), test_board.get_byte_and_bit(&Cell::new(9, 1)));
-
-        let mut test_vec = vec![0u8; 7];
-        test_vec[0] = 255;
-        test_vec[1] = 19;
-        test_vec[2] = 7;
-        test_vec[3] = 113;
-        test_board = Board {
-            size: 5,
-            field: Base64VecU8::from(test_vec),
-        };
-
-        assert_eq!((255, 0, 6), test_board.get_byte_and_bit(&Cell::new(3, 0)));
-        assert_eq!((7, 2, 6), test_board.get_byte_and_bit(&Cell::new(1, 2)));
-        assert_eq!((19, 1, 0), test_board.get_byte_and_bit(&Cell::new(4, 0)));
-        assert_eq!((0, 6, 0), test_board.get_byte_and_bit(&Cell::new(4, 4)));
-    }
-
-    #[test]
-    #[should_panic]
-    fn test_get_cell_out_of_bounds() {
-        Board::new(11).get_cell(&Cell::new(5, 11));
-    }
-
-    #[test]
-    fn test_get_cell() {
-        let mut test_vec = vec![0u8; 7];
-        test_vec[0] = 255;
-        test_vec[1] = 32;
-        test_vec[2] = 7;
-        test_vec[3] = 113;
-        let test_board = Board {
-            size: 5,
-            field: Base64VecU8::from(test_vec),
-        };
-
-        assert_eq!(3, test_board.get_cell(&Cell::new(3, 0)));
-        assert_eq!(1, test_board.get_cell(&Cell::new(4, 1)));
-        assert_eq!(3, test_board.get_cell(&Cell::new(4, 2)));
-        assert_eq!(2, test_board.get_cell(&Cell::new(1, 1)));
-    }
-
-    #[test]
-    #[should_panic]
-    fn test_set_cell_out_of_bounds() {
-        Board::new(5).set_cell(&Cell::new(6, 3), 1);
-    }
-
-    #[test]
-    #[should_panic]
-    fn test_set_cell_too_big_value() {
-        Board::new(4).set_cell(&Cell::new(1, 1), 3);
-    }
-
-    #[test]
-    fn test_set_sell() {
-        let mut test_board = Board::new(11);
-        let test_cell = Cell::new(2, 5);
-
-        test_board.set_cell(&test_cell, 1);
-        assert_eq!(1, test_board.get_cell(&test_cell));
-
-        test_board.set_cell(&test_cell, 0);
-        assert_eq!(0, test_board.get_cell(&test_cell));
-
-        test_board.set_cell(&test_cell, 2);
-        assert_eq!(2, test_board.get_cell(&test_cell));
-
-        test_board.set_cell(&Cell::new(10, 10), 1);
-        test_board.set_cell(&Cell::new(3, 5), 1);
-
-        assert_eq!(2, test_board.get_cell(&Cell::new(2, 5)));
-        assert_eq!(1, test_board.get_cell(&Cell::new(10, 10)));
-        assert_eq!(1, test_board.get_cell(&Cell::new(3, 5)));
-    }
-
-    #[test]
-    fn test_get_coords() {
-        let mut test_board = Board::new(5);
-        assert_eq!(Cell::new(0, 1), test_board.get_coords(10));
-        assert_eq!(Cell::new(0, 0), test_board.get_coords(0));
-        assert_eq!(Cell::new(0, 0), test_board.get_coords(1));
-        assert_eq!(Cell::new(4, 4), test_board.get_coords(48));
-
-        test_board = Board::new(15);
-        let mut test_cell = Cell::new(11, 7);
-        let (_, mut byte, mut bit) = test_board.get_byte_and_bit(&test_cell);
-        assert_eq!(test_cell, test_board.get_coords(byte * 8 + bit));
-
-        test_cell = Cell::new(0, 14);
-        (_, byte, bit) = test_board.get_byte_and_bit(&test_cell);
-        assert_eq!(test_cell, test_board.get_coords(byte * 8 + bit));
+    fn test_log_changes() {
+        let old_board = Board::new(26, 26).unwrap();
+        let mut test_board = old_board.clone();
+        test_board.set_cell(&Cell::new(25, 25), 1);
+        test_board.set_cell(&Cell::new(2, 0), 2);
+        test_board.log_changes(&old_board);
+        assert_eq!(
+            get_logs(),
+            vec!["Changed cells: c1 . -> B, z26 . -> R.".to_string()]
+        );
 
-        test_cell = Cell::new(10, 2);
-        (_, byte, bit) = test_board.get_byte_and_bit(&test_cell);
-        assert_eq!(test_cell, test_board.get_coords(byte * 8 + bit));
+        let old_board = Board::new(3, 3).unwrap();
+        let mut test_board = old_board.clone();
+        test_board.set_cell(&Cell::new(1, 1), 1);
+        test_board.log_changes(&old_board);
+        assert_eq!(get_logs().len(), 1 + 2 * 4);
     }
 }
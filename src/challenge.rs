@@ -0,0 +1,262 @@
+use std::ops::Bound;
+
+use near_sdk::Balance;
+
+use crate::errors::{ensure, HexError};
+use crate::move_record::max_history_storage;
+use crate::utils::{refund_storage, DEFAULT_PAGE_SIZE};
+use crate::*;
+
+pub type ChallengeIndex = u64;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Challenge {
+    pub creator: AccountId,
+    pub settings: GameSettings,
+}
+
+impl Challenge {
+    pub fn matches(
+        &self,
+        field_size: Option<usize>,
+        min_bid: Option<Balance>,
+        max_bid: Option<Balance>,
+    ) -> bool {
+        let bid = self.settings.bid.map_or(0, u128::from);
+        field_size.is_none_or(|size| size == self.settings.field_size)
+            && min_bid.is_none_or(|min_bid| bid >= min_bid)
+            && max_bid.is_none_or(|max_bid| bid <= max_bid)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    #[handle_result]
+    pub fn create_open_challenge(
+        &mut self,
+        options: Option<GameOptions>,
+    ) -> Result<ChallengeIndex, HexError> {
+        let settings = GameSettings::new(options.unwrap_or_default())?;
+        let initial_storage_usage = env::storage_usage();
+
+        let id = self.next_challenge_id;
+        let challenge = Challenge {
+            creator: env::predecessor_account_id(),
+            settings,
+        };
+        self.challenges.insert(&id, &challenge);
+        self.next_challenge_id += 1;
+
+        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
+        refund_deposit(required_storage_in_bytes);
+
+        env::log_str(&format!("Created open challenge {}.", id));
+        Ok(id)
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn join_challenge(&mut self, id: ChallengeIndex) -> Result<GameIndex, HexError> {
+        let challenge = self
+            .challenges
+            .get(&id)
+            .ok_or(HexError::ChallengeNotFound)?;
+        let account_id = env::predecessor_account_id();
+        ensure(account_id != challenge.creator, HexError::OwnChallenge)?;
+
+        let storage_before_removal = env::storage_usage();
+        self.challenges.remove(&id);
+        refund_storage(
+            challenge.creator.clone(),
+            storage_before_removal - env::storage_usage(),
+        );
+
+        let initial_storage_usage = env::storage_usage();
+        let settings = challenge.settings;
+        let mut game_with_data = GameWithData::new(
+            challenge.creator,
+            account_id,
+            settings.field_size,
+            settings.field_height,
+            settings.playtime,
+            settings.move_timeout,
+        );
+        game_with_data.game.allow_takebacks = settings.allow_takebacks;
+        game_with_data.game.swap_rule = settings.swap_rule;
+        game_with_data.game.misere = settings.misere;
+        game_with_data.set_topology(settings.topology)?;
+        game_with_data.game.start();
+        let index = self.insert_game(&game_with_data, &settings);
+
+        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage
+            + max_history_storage(&game_with_data.game.board);
+        refund_deposit(required_storage_in_bytes);
+
+        env::log_str(&format!(
+            "Challenge {} accepted, created game {}.",
+            id, index
+        ));
+        Ok(index)
+    }
+
+    #[handle_result]
+    pub fn cancel_challenge(&mut self, id: ChallengeIndex) -> Result<(), HexError> {
+        let challenge = self
+            .challenges
+            .get(&id)
+            .ok_or(HexError::ChallengeNotFound)?;
+        ensure(
+            env::predecessor_account_id() == challenge.creator,
+            HexError::NotChallengeCreator,
+        )?;
+
+        let storage_before_removal = env::storage_usage();
+        self.challenges.remove(&id);
+        refund_storage(
+            challenge.creator,
+            storage_before_removal - env::storage_usage(),
+        );
+        env::log_str(&format!("Challenge {} cancelled.", id));
+        Ok(())
+    }
+
+    pub fn get_challenge(&self, id: ChallengeIndex) -> Option<Challenge> {
+        self.challenges.get(&id)
+    }
+
+    /// Lists the challenges with ids from `from_index` on. Challenges are kept
+    /// ordered by id, so pages don't shift when others are joined.
+    pub fn get_open_challenges(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+        field_size: Option<usize>,
+        min_bid: Option<U128>,
+        max_bid: Option<U128>,
+    ) -> Vec<(ChallengeIndex, Challenge)> {
+        let (min_bid, max_bid) = (min_bid.map(u128::from), max_bid.map(u128::from));
+        self.challenges
+            .range((Bound::Included(from_index.unwrap_or(0)), Bound::Unbounded))
+            .filter(|(_, challenge)| challenge.matches(field_size, min_bid, max_bid))
+            .take(limit.unwrap_or(DEFAULT_PAGE_SIZE) as usize)
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod challenge_tests {
+    use near_sdk::{test_utils::accounts, testing_env, ONE_NEAR};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_join_challenge() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = Contract::new();
+        let id = contract.create_open_challenge(Some(with_size(7))).unwrap();
+        assert_eq!(contract.get_challenge(id).unwrap().creator, accounts(0));
+
+        testing_env!(get_context(accounts(1)));
+        let index = contract.join_challenge(id).unwrap();
+        assert!(contract.get_challenge(id).is_none());
+
+        let game = contract.get_game(index).unwrap();
+        assert_eq!(game.first_player, accounts(0));
+        assert_eq!(game.second_player, accounts(1));
+        assert_eq!(game.board.width, 7);
+        assert!(game.is_started);
+    }
+
+    #[test]
+    fn test_join_own_challenge() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = Contract::new();
+        let id = contract.create_open_challenge(None).unwrap();
+        assert_eq!(
+            contract.join_challenge(id).err(),
+            Some(HexError::OwnChallenge)
+        );
+    }
+
+    #[test]
+    fn test_join_cancelled_challenge() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = Contract::new();
+        let id = contract.create_open_challenge(None).unwrap();
+        contract.cancel_challenge(id).unwrap();
+
+        testing_env!(get_context(accounts(1)));
+        assert_eq!(
+            contract.join_challenge(id).err(),
+            Some(HexError::ChallengeNotFound)
+        );
+    }
+
+    #[test]
+    fn test_get_open_challenges() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = Contract::new();
+        contract.create_open_challenge(Some(with_size(11))).unwrap();
+        contract
+            .create_open_challenge(Some(GameOptions {
+                field_size: Some(11),
+                bid: Some(U128(5 * ONE_NEAR)),
+                ..Default::default()
+            }))
+            .unwrap();
+        contract
+            .create_open_challenge(Some(GameOptions {
+                field_size: Some(13),
+                bid: Some(U128(10 * ONE_NEAR)),
+                ..Default::default()
+            }))
+            .unwrap();
+        contract
+            .create_open_challenge(Some(GameOptions {
+                field_size: Some(11),
+                bid: Some(U128(20 * ONE_NEAR)),
+                ..Default::default()
+            }))
+            .unwrap();
+
+        let ids = |challenges: Vec<(ChallengeIndex, Challenge)>| {
+            challenges.into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(contract.get_open_challenges(None, None, None, None, None)),
+            vec![0, 1, 2, 3]
+        );
+        assert_eq!(
+            ids(contract.get_open_challenges(Some(1), Some(2), None, None, None)),
+            vec![1, 2]
+        );
+        assert_eq!(
+            ids(contract.get_open_challenges(None, None, Some(11), None, None)),
+            vec![0, 1, 3]
+        );
+        assert_eq!(
+            ids(contract.get_open_challenges(
+                None,
+                None,
+                None,
+                Some(U128(5 * ONE_NEAR)),
+                Some(U128(10 * ONE_NEAR))
+            )),
+            vec![1, 2]
+        );
+        assert_eq!(
+            ids(contract.get_open_challenges(Some(2), None, Some(11), Some(U128(ONE_NEAR)), None)),
+            vec![3]
+        );
+
+        testing_env!(get_context(accounts(1)));
+        contract.join_challenge(1).unwrap();
+        assert_eq!(
+            ids(contract.get_open_challenges(Some(1), Some(2), None, None, None)),
+            vec![2, 3]
+        );
+    }
+}
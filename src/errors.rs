@@ -0,0 +1,205 @@
+use std::fmt;
+
+use hex_rules::RulesError;
+use near_sdk::FunctionError;
+
+/// Every way a call to the contract can fail. Failed calls panic with
+/// `<code>: <message>`, e.g. `ERR_CELL_FILLED: Cell is already filled.`, and
+/// the codes are kept stable so that clients can match on them.
+#[derive(FunctionError, Clone, Copy, PartialEq, Debug)]
+pub enum HexError {
+    Rules(RulesError),
+    GameNotFound,
+    GameFinished,
+    GameNotStarted,
+    GameAlreadyStarted,
+    NotYourGame,
+    NotInvited,
+    NotAPlayer,
+    NotYourTurn,
+    InvalidMoveArgs,
+    BetsNotPlaced,
+    NoMoveTimeout,
+    NotWaitingPlayer,
+    OpponentHasTime,
+    TakebacksDisabled,
+    NoMoveToTakeBack,
+    TakebackAlreadyRequested,
+    NotYourMove,
+    TakebackNotRequested,
+    NotOpponent,
+    MoveAlreadyCommitted,
+    NoCommittedMove,
+    CommitmentMismatch,
+    ChallengeNotFound,
+    OwnChallenge,
+    NotChallengeCreator,
+    InvalidPlaytime,
+    PlaytimeWithoutBid,
+    InvalidBid,
+    InvalidMoveTimeout,
+    MoveTimeoutWithBid,
+    InvalidInitialPosition,
+    SwapWithInitialPosition,
+    InvalidSwapRule,
+    InvalidHoles,
+    NotBettingGame,
+    NotEnoughGas,
+    NotEnoughDeposit,
+    InvalidBet,
+    WrongResultsCount,
+    WrongValueReceived,
+    CallFailed,
+}
+
+impl HexError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            HexError::Rules(RulesError::InvalidSize) => "ERR_INVALID_FIELD_SIZE",
+            HexError::Rules(RulesError::OutOfBounds) => "ERR_CELL_OUT_OF_BOUNDS",
+            HexError::Rules(RulesError::CellFilled) => "ERR_CELL_FILLED",
+            HexError::Rules(RulesError::CellBlocked) => "ERR_CELL_BLOCKED",
+            HexError::Rules(RulesError::SwapNotAllowed) => "ERR_SWAP_NOT_ALLOWED",
+            HexError::Rules(RulesError::SwapDisabled) => "ERR_SWAP_DISABLED",
+            HexError::Rules(RulesError::GameFinished) | HexError::GameFinished => {
+                "ERR_GAME_FINISHED"
+            }
+            HexError::Rules(RulesError::NothingToUndo) | HexError::NoMoveToTakeBack => {
+                "ERR_NO_MOVE_TO_TAKE_BACK"
+            }
+            HexError::GameNotFound => "ERR_GAME_NOT_FOUND",
+            HexError::GameNotStarted => "ERR_GAME_NOT_STARTED",
+            HexError::GameAlreadyStarted => "ERR_GAME_ALREADY_STARTED",
+            HexError::NotYourGame => "ERR_NOT_YOUR_GAME",
+            HexError::NotInvited => "ERR_NOT_INVITED",
+            HexError::NotAPlayer => "ERR_NOT_A_PLAYER",
+            HexError::NotYourTurn => "ERR_NOT_YOUR_TURN",
+            HexError::InvalidMoveArgs => "ERR_INVALID_MOVE_ARGS",
+            HexError::BetsNotPlaced => "ERR_BETS_NOT_PLACED",
+            HexError::NoMoveTimeout => "ERR_NO_MOVE_TIMEOUT",
+            HexError::NotWaitingPlayer => "ERR_NOT_WAITING_PLAYER",
+            HexError::OpponentHasTime => "ERR_OPPONENT_HAS_TIME",
+            HexError::TakebacksDisabled => "ERR_TAKEBACKS_DISABLED",
+            HexError::TakebackAlreadyRequested => "ERR_TAKEBACK_ALREADY_REQUESTED",
+            HexError::NotYourMove => "ERR_NOT_YOUR_MOVE",
+            HexError::TakebackNotRequested => "ERR_TAKEBACK_NOT_REQUESTED",
+            HexError::NotOpponent => "ERR_NOT_OPPONENT",
+            HexError::MoveAlreadyCommitted => "ERR_MOVE_ALREADY_COMMITTED",
+            HexError::NoCommittedMove => "ERR_NO_COMMITTED_MOVE",
+            HexError::CommitmentMismatch => "ERR_COMMITMENT_MISMATCH",
+            HexError::ChallengeNotFound => "ERR_CHALLENGE_NOT_FOUND",
+            HexError::OwnChallenge => "ERR_OWN_CHALLENGE",
+            HexError::NotChallengeCreator => "ERR_NOT_CHALLENGE_CREATOR",
+            HexError::InvalidPlaytime => "ERR_INVALID_PLAYTIME",
+            HexError::PlaytimeWithoutBid => "ERR_PLAYTIME_WITHOUT_BID",
+            HexError::InvalidBid => "ERR_INVALID_BID",
+            HexError::InvalidMoveTimeout => "ERR_INVALID_MOVE_TIMEOUT",
+            HexError::MoveTimeoutWithBid => "ERR_MOVE_TIMEOUT_WITH_BID",
+            HexError::InvalidInitialPosition => "ERR_INVALID_INITIAL_POSITION",
+            HexError::SwapWithInitialPosition => "ERR_SWAP_WITH_INITIAL_POSITION",
+            HexError::InvalidSwapRule => "ERR_INVALID_SWAP_RULE",
+            HexError::InvalidHoles => "ERR_INVALID_HOLES",
+            HexError::NotBettingGame => "ERR_NOT_BETTING_GAME",
+            HexError::NotEnoughGas => "ERR_NOT_ENOUGH_GAS",
+            HexError::NotEnoughDeposit => "ERR_NOT_ENOUGH_DEPOSIT",
+            HexError::InvalidBet => "ERR_INVALID_BET",
+            HexError::WrongResultsCount => "ERR_WRONG_RESULTS_COUNT",
+            HexError::WrongValueReceived => "ERR_WRONG_VAL_RECEIVED",
+            HexError::CallFailed => "ERR_CALL_FAILED",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            HexError::Rules(error) => error.message(),
+            HexError::GameNotFound => "Game doesn't exist.",
+            HexError::GameFinished => "Game is already finished!",
+            HexError::GameNotStarted => "Game hasn't been accepted yet.",
+            HexError::GameAlreadyStarted => "Game is already accepted.",
+            HexError::NotYourGame => "You can only create games you take part in.",
+            HexError::NotInvited => "Only the invited player can accept the game.",
+            HexError::NotAPlayer => "Only players of the game can do this.",
+            HexError::NotYourTurn => "It's not your turn.",
+            HexError::InvalidMoveArgs => "Incorrect move args.",
+            HexError::BetsNotPlaced => "Players should deposit their bets before game start.",
+            HexError::NoMoveTimeout => "Game doesn't have a move timeout.",
+            HexError::NotWaitingPlayer => "Only the waiting player can claim a timeout.",
+            HexError::OpponentHasTime => "Opponent still has time to make a move.",
+            HexError::TakebacksDisabled => "Takebacks are disabled in this game.",
+            HexError::NoMoveToTakeBack => "There is no move to take back.",
+            HexError::TakebackAlreadyRequested => "Takeback is already requested.",
+            HexError::NotYourMove => "You can only take back your own move.",
+            HexError::TakebackNotRequested => "Takeback wasn't requested.",
+            HexError::NotOpponent => "Only the opponent can accept the takeback.",
+            HexError::MoveAlreadyCommitted => "The move is already committed, reveal it.",
+            HexError::NoCommittedMove => "There is no committed move.",
+            HexError::CommitmentMismatch => "The move doesn't match the committed hash.",
+            HexError::ChallengeNotFound => "Challenge doesn't exist.",
+            HexError::OwnChallenge => "You can't join your own challenge.",
+            HexError::NotChallengeCreator => "Only the creator can cancel the challenge.",
+            HexError::InvalidPlaytime => "Game playtime can't be too small or too big.",
+            HexError::PlaytimeWithoutBid => {
+                "You can't make game with time control without betting."
+            }
+            HexError::InvalidBid => "Bid can't be too small or too big.",
+            HexError::InvalidMoveTimeout => "Move timeout can't be too small or too big.",
+            HexError::MoveTimeoutWithBid => "Games with betting are timed by their playtime.",
+            HexError::InvalidInitialPosition => "Initial position can't be already won.",
+            HexError::SwapWithInitialPosition => {
+                "Games with an initial position can't have a swap rule."
+            }
+            HexError::InvalidSwapRule => "Pieces can only be swapped on square boards.",
+            HexError::InvalidHoles => "Holes can't take more than half of the board.",
+            HexError::NotBettingGame => "There's no betting game with such index.",
+            HexError::NotEnoughGas => "You should attach more gas.",
+            HexError::NotEnoughDeposit => "Attached deposit doesn't cover the bet and the fee.",
+            HexError::InvalidBet => "Invalid bet.",
+            HexError::WrongResultsCount => "Unexpected number of promise results.",
+            HexError::WrongValueReceived => "Unexpected promise result.",
+            HexError::CallFailed => "Cross-contract call failed.",
+        }
+    }
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl From<RulesError> for HexError {
+    fn from(error: RulesError) -> Self {
+        HexError::Rules(error)
+    }
+}
+
+/// Fails with the error unless the condition holds, like `require!`.
+pub fn ensure(condition: bool, error: HexError) -> Result<(), HexError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod errors_tests {
+    use super::*;
+
+    #[test]
+    fn test_error_message() {
+        assert_eq!(
+            HexError::NotYourTurn.to_string(),
+            "ERR_NOT_YOUR_TURN: It's not your turn."
+        );
+        assert_eq!(
+            HexError::from(RulesError::CellFilled).to_string(),
+            "ERR_CELL_FILLED: Cell is already filled."
+        );
+        assert_eq!(ensure(true, HexError::GameNotFound), Ok(()));
+        assert_eq!(
+            ensure(false, HexError::GameNotFound),
+            Err(HexError::GameNotFound)
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use near_sdk::serde_json;
+
+use crate::*;
+
+pub const EVENT_STANDARD: &str = "hex-game";
+pub const EVENT_VERSION: &str = "1.0.0";
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    Connection,
+    Resignation,
+    Timeout,
+    OutOfTime,
+    Declined,
+    Draw,
+}
+
+/// Game events logged in the NEP-297 format, i.e. as
+/// `EVENT_JSON:{"standard":"hex-game","version":"1.0.0","event":...,"data":...}`.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum HexEvent {
+    GameCreated {
+        game_id: GameIndex,
+        first_player: AccountId,
+        second_player: AccountId,
+        field_size: usize,
+        field_height: usize,
+        bid: Option<U128>,
+        playtime: Option<u32>,
+        move_timeout: Option<BlockHeight>,
+        swap_rule: SwapRule,
+        misere: bool,
+        topology: Topology,
+    },
+    MoveMade {
+        game_id: GameIndex,
+        player: AccountId,
+        move_type: MoveType,
+        cell: Cell,
+        turn: usize,
+    },
+    SwapApplied {
+        game_id: GameIndex,
+        player: AccountId,
+        from: Cell,
+        to: Cell,
+        rule: SwapRule,
+        turn: usize,
+    },
+    MoveCommitted {
+        game_id: GameIndex,
+        player: AccountId,
+        turn: usize,
+    },
+    TakebackRequested {
+        game_id: GameIndex,
+        player: AccountId,
+        turn: usize,
+    },
+    TakebackAccepted {
+        game_id: GameIndex,
+        player: AccountId,
+        turn: usize,
+    },
+    BidPlaced {
+        game_id: GameIndex,
+        player: AccountId,
+        amount: U128,
+    },
+    GameFinished {
+        game_id: GameIndex,
+        winner: Option<AccountId>,
+        reason: FinishReason,
+    },
+    PayoutSent {
+        game_id: GameIndex,
+        receiver: AccountId,
+        amount: U128,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a HexEvent,
+}
+
+impl HexEvent {
+    pub fn game_finished(game_id: GameIndex, game: &Game, reason: FinishReason) -> Self {
+        Self::GameFinished {
+            game_id,
+            winner: game
+                .winner
+                .as_ref()
+                .map(|winner| game.player_account(winner).clone()),
+            reason,
+        }
+    }
+
+    pub fn to_log_string(&self) -> String {
+        let log = EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_VERSION,
+            event: self,
+        };
+        format!("EVENT_JSON:{}", serde_json::to_string(&log).unwrap())
+    }
+
+    pub fn emit(&self) {
+        env::log_str(&self.to_log_string());
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod events_tests {
+    use near_sdk::test_utils::accounts;
+
+    use super::*;
+
+    #[test]
+    fn test_move_made_log() {
+        let event = HexEvent::MoveMade {
+            game_id: 3,
+            player: accounts(0),
+            move_type: MoveType::PLACE,
+            cell: Cell::new(2, 4),
+            turn: 1,
+        };
+        assert_eq!(
+            event.to_log_string(),
+            r#"EVENT_JSON:{"standard":"hex-game","version":"1.0.0","event":"move_made","data":{"game_id":3,"player":"alice","move_type":"PLACE","cell":{"x":2,"y":4},"turn":1}}"#
+        );
+    }
+
+    #[test]
+    fn test_game_finished_log() {
+        let event = HexEvent::GameFinished {
+            game_id: 0,
+            winner: Some(accounts(1)),
+            reason: FinishReason::OutOfTime,
+        };
+        assert_eq!(
+            event.to_log_string(),
+            r#"EVENT_JSON:{"standard":"hex-game","version":"1.0.0","event":"game_finished","data":{"game_id":0,"winner":"bob","reason":"out_of_time"}}"#
+        );
+    }
+}
@@ -1,11 +1,15 @@
+use hex_rules::{player_color, Board, SwapRule, BLOCKED};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, AccountId, BlockHeight};
+use near_sdk::{env, AccountId, BlockHeight, FunctionError};
 
-use crate::board::Board;
-use crate::cell::Cell;
+use crate::errors::HexError;
+use crate::events::FinishReason;
+use crate::initial_position::InitialPosition;
+use crate::move_commitment::MoveCommitment;
 
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(crate = "near_sdk::serde")]
 pub enum Player {
     First,
@@ -22,71 +26,174 @@ pub struct Game {
     pub current_block_height: BlockHeight,
     pub prev_block_height: BlockHeight,
     pub is_started: bool,
+    pub invited_player: Player,
     pub is_finished: bool,
     pub playtime: Option<u32>,
+    pub move_timeout: Option<BlockHeight>,
     pub winner: Option<Player>,
+    /// How the game ended, once it's finished.
+    pub finish_reason: Option<FinishReason>,
+    pub allow_takebacks: bool,
+    pub takeback_requested_by: Option<Player>,
+    pub sides_swapped: bool,
+    pub swap_rule: SwapRule,
+    pub initial_position: Option<InitialPosition>,
+    /// Misère Hex (Rex): the player who connects their borders loses.
+    pub misere: bool,
+    pub commitment: Option<MoveCommitment>,
 }
 
 pub type GameIndex = u64;
 
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum GameStatus {
+    Pending,
+    Active,
+    Finished,
+    Declined,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GameSummary {
+    pub first_player: AccountId,
+    pub second_player: AccountId,
+    pub field_size: usize,
+    pub field_height: usize,
+    pub turn: usize,
+    pub status: GameStatus,
+    pub winner: Option<Player>,
+    pub bid: Option<U128>,
+}
+
 impl Game {
     pub fn new(
         first_player: AccountId,
         second_player: AccountId,
         field_size: usize,
+        field_height: usize,
         playtime: Option<u32>,
+        move_timeout: Option<BlockHeight>,
     ) -> Self {
+        let board = Board::new(field_size, field_height)
+            .unwrap_or_else(|error| HexError::from(error).panic());
         Self {
             first_player,
             second_player,
             turn: 0,
-            board: Board::new(field_size),
+            swap_rule: SwapRule::default_for(&board),
+            board,
             current_block_height: env::block_height(),
             prev_block_height: 0,
             is_started: false,
+            invited_player: Player::Second,
             is_finished: false,
             playtime,
+            move_timeout,
             winner: None,
+            finish_reason: None,
+            allow_takebacks: false,
+            takeback_requested_by: None,
+            sides_swapped: false,
+            initial_position: None,
+            misere: false,
+            commitment: None,
         }
     }
 
-    pub fn place_counter(&mut self, cell: &Cell, player: u8) {
-        self.board.set_cell(cell, player);
-        self.turn += 1;
-        if env::block_height() != self.current_block_height {
-            self.prev_block_height = self.current_block_height;
-            self.current_block_height = env::block_height();
+    pub fn status(&self) -> GameStatus {
+        match (self.is_started, self.is_finished) {
+            (false, false) => GameStatus::Pending,
+            (true, false) => GameStatus::Active,
+            (true, true) => GameStatus::Finished,
+            (false, true) => GameStatus::Declined,
         }
     }
 
-    pub fn swap_rule(&mut self) -> Cell {
-        let non_zero_byte = self
-            .board
-            .field
-            .0
-            .iter()
-            .enumerate()
-            .find(|(_, &x)| x != 0)
-            .unwrap();
-        let mut bit_number = 8 * non_zero_byte.0;
-        if non_zero_byte.1 & 4 == 4 {
-            bit_number += 2;
-        } else if non_zero_byte.1 & 16 == 16 {
-            bit_number += 4;
-        } else if non_zero_byte.1 & 64 == 64 {
-            bit_number += 6;
+    pub fn summary(&self, bid: Option<U128>) -> GameSummary {
+        GameSummary {
+            first_player: self.first_player.clone(),
+            second_player: self.second_player.clone(),
+            field_size: self.board.width,
+            field_height: self.board.height,
+            turn: self.turn,
+            status: self.status(),
+            winner: self.winner.clone(),
+            bid,
         }
-        let cell = self.board.get_coords(bit_number);
+    }
+
+    pub fn player_account(&self, player: &Player) -> &AccountId {
+        match player {
+            Player::First => &self.first_player,
+            Player::Second => &self.second_player,
+        }
+    }
+
+    pub fn player_to_move(&self) -> &AccountId {
+        if self.turn.is_multiple_of(2) {
+            &self.first_player
+        } else {
+            &self.second_player
+        }
+    }
+
+    /// Color of the stones placed by the player, see `hex_rules::player_color`.
+    pub fn player_color(&self, player: &Player) -> u8 {
+        player_color(matches!(player, Player::First), self.sides_swapped)
+    }
+
+    pub fn color_to_move(&self) -> u8 {
+        if self.turn.is_multiple_of(2) {
+            self.player_color(&Player::First)
+        } else {
+            self.player_color(&Player::Second)
+        }
+    }
+
+    /// Whether the player to move may apply the swap rule instead of placing
+    /// a stone. Swapping pieces isn't possible if the mirrored cell is a hole.
+    pub fn can_swap(&self) -> bool {
+        let onto_hole = || {
+            self.board
+                .cells()
+                .find(|cell| matches!(self.board.get_cell(cell), 1 | 2))
+                .is_some_and(|cell| self.board.get_cell(&cell.symm()) == BLOCKED)
+        };
+        self.turn == 1
+            && self.swap_rule != SwapRule::NoSwap
+            && !(self.swap_rule == SwapRule::SwapPieces && onto_hole())
+    }
 
-        self.board.set_cell(&cell, 0);
-        self.board.set_cell(&cell.symm(), 2);
-        self.turn += 1;
+    pub fn invited_account(&self) -> &AccountId {
+        self.player_account(&self.invited_player)
+    }
+
+    pub fn start(&mut self) {
+        self.is_started = true;
+        self.current_block_height = env::block_height();
+    }
+
+    /// Ends the game, in a draw if there's no winner.
+    pub fn finish(&mut self, winner: Option<Player>, reason: FinishReason) {
+        self.is_finished = true;
+        self.winner = winner;
+        self.finish_reason = Some(reason);
+    }
+
+    /// Remembers the block of the last move, keeping the previous one if the
+    /// moves are made in different blocks.
+    pub fn update_block_height(&mut self) {
         if env::block_height() != self.current_block_height {
             self.prev_block_height = self.current_block_height;
             self.current_block_height = env::block_height();
         }
+    }
 
-        cell
+    pub fn move_deadline(&self) -> Option<BlockHeight> {
+        self.move_timeout
+            .map(|timeout| self.current_block_height + timeout)
     }
 }
 
@@ -97,113 +204,89 @@ mod game_tests {
         testing_env,
     };
 
-    use crate::cell::Cell;
+    use hex_rules::{Cell, SwapRule, BLOCKED};
 
-    use super::Game;
+    use super::{Game, GameStatus};
 
     fn get_context() -> VMContextBuilder {
         VMContextBuilder::new()
     }
 
     #[test]
-    #[should_panic]
-    fn test_place_counter_wrong_player_1() {
-        let mut game = Game::new(accounts(0), accounts(1), 11, None);
-        game.place_counter(&Cell::new(1, 1), 2);
-    }
+    fn test_update_block_height() {
+        testing_env!(get_context().block_index(0).build());
+        let mut game = Game::new(accounts(0), accounts(1), 11, 11, None, None);
+        game.update_block_height();
+        game.update_block_height();
+        assert_eq!(game.current_block_height, 0);
+        assert_eq!(game.prev_block_height, 0);
 
-    #[test]
-    #[should_panic]
-    fn test_place_counter_wrong_player_2() {
-        let mut game = Game::new(accounts(0), accounts(1), 11, None);
-        game.place_counter(&Cell::new(1, 1), 1);
-        game.place_counter(&Cell::new(2, 1), 1);
-    }
+        testing_env!(get_context().block_index(100).build());
+        game.update_block_height();
+        game.update_block_height();
+        assert_eq!(game.current_block_height, 100);
+        assert_eq!(game.prev_block_height, 0);
 
-    #[test]
-    #[should_panic]
-    fn test_place_counter_cell_is_already_filled() {
-        let mut game = Game::new(accounts(0), accounts(1), 11, None);
-        game.place_counter(&Cell::new(1, 1), 1);
-        game.place_counter(&Cell::new(1, 1), 2);
+        testing_env!(get_context().block_index(130).build());
+        game.update_block_height();
+        assert_eq!(game.current_block_height, 130);
+        assert_eq!(game.prev_block_height, 100);
     }
 
     #[test]
-    fn test_place_counter() {
-        testing_env!(get_context().block_index(0).build());
+    fn test_move_deadline() {
+        testing_env!(get_context().block_index(10).build());
 
-        let mut game = Game::new(accounts(0), accounts(1), 11, None);
-        game.place_counter(&Cell::new(1, 1), 1);
-        game.place_counter(&Cell::new(1, 2), 2);
-        game.place_counter(&Cell::new(10, 7), 1);
-        assert_eq!(game.current_block_height, 0);
-        assert_eq!(game.prev_block_height, 0);
+        let mut game = Game::new(accounts(0), accounts(1), 11, 11, None, Some(300));
+        assert_eq!(game.move_deadline(), Some(310));
 
         testing_env!(get_context().block_index(100).build());
-        game.place_counter(&Cell::new(5, 9), 2);
-        game.place_counter(&Cell::new(3, 7), 1);
-        assert_eq!(game.current_block_height, 100);
-        assert_eq!(game.prev_block_height, 0);
+        game.update_block_height();
+        assert_eq!(game.move_deadline(), Some(400));
 
-        assert_eq!(game.first_player, accounts(0));
-        assert_eq!(game.second_player, accounts(1));
-        assert_eq!(game.turn, 5);
-        for i in 0..11 {
-            for j in 0..11 {
-                let cell = Cell::new(i, j);
-                if cell == Cell::new(1, 1) || cell == Cell::new(10, 7) || cell == Cell::new(3, 7) {
-                    assert_eq!(game.board.get_cell(&cell), 1);
-                } else if cell == Cell::new(1, 2) || cell == Cell::new(5, 9) {
-                    assert_eq!(game.board.get_cell(&cell), 2);
-                } else {
-                    assert_eq!(game.board.get_cell(&cell), 0);
-                }
-            }
-        }
+        let game = Game::new(accounts(0), accounts(1), 11, 11, None, None);
+        assert_eq!(game.move_deadline(), None);
     }
 
     #[test]
-    #[should_panic]
-    fn test_swap_rule_too_early() {
-        let mut game = Game::new(accounts(0), accounts(1), 11, None);
-        game.swap_rule();
+    fn test_start() {
+        testing_env!(get_context().block_index(10).build());
+        let mut game = Game::new(accounts(0), accounts(1), 11, 11, None, Some(300));
+        assert!(!game.is_started);
+        assert_eq!(game.invited_account(), &accounts(1));
+
+        testing_env!(get_context().block_index(50).build());
+        game.start();
+        assert!(game.is_started);
+        assert_eq!(game.move_deadline(), Some(350));
     }
 
     #[test]
-    #[should_panic]
-    fn test_swap_rule_too_late() {
-        let mut game = Game::new(accounts(0), accounts(1), 11, None);
-        game.place_counter(&Cell::new(2, 5), 1);
-        game.place_counter(&Cell::new(10, 7), 2);
-        game.swap_rule();
+    fn test_status() {
+        let mut game = Game::new(accounts(0), accounts(1), 11, 11, None, None);
+        assert!(game.status() == GameStatus::Pending);
+        game.start();
+        assert!(game.status() == GameStatus::Active);
+        game.is_finished = true;
+        assert!(game.status() == GameStatus::Finished);
+
+        let mut game = Game::new(accounts(0), accounts(1), 11, 11, None, None);
+        game.is_finished = true;
+        assert!(game.status() == GameStatus::Declined);
     }
 
     #[test]
-    fn test_swap_rule() {
-        let mut game = Game::new(accounts(0), accounts(1), 11, None);
-        game.place_counter(&Cell::new(10, 7), 1);
-
-        let c = game.swap_rule();
-        assert_eq!(Cell::new(10, 7), c);
-        assert_eq!(0, game.board.get_cell(&Cell::new(10, 7)));
-        assert_eq!(2, game.board.get_cell(&Cell::new(7, 10)));
-
-        game.place_counter(&Cell::new(1, 1), 1);
-        game.place_counter(&Cell::new(5, 9), 2);
-        game.place_counter(&Cell::new(3, 7), 1);
-
-        assert_eq!(game.turn, 5);
-        for i in 0..11 {
-            for j in 0..11 {
-                let cell = Cell::new(i, j);
-                if cell == Cell::new(1, 1) || cell == Cell::new(3, 7) {
-                    assert_eq!(game.board.get_cell(&cell), 1);
-                } else if cell == Cell::new(7, 10) || cell == Cell::new(5, 9) {
-                    assert_eq!(game.board.get_cell(&cell), 2);
-                } else {
-                    assert_eq!(game.board.get_cell(&cell), 0);
-                }
-            }
-        }
+    fn test_can_swap() {
+        let mut game = Game::new(accounts(0), accounts(1), 3, 3, None, None);
+        game.swap_rule = SwapRule::SwapPieces;
+        assert!(!game.can_swap());
+        game.board.set_cell(&Cell::new(0, 1), 1);
+        game.turn = 1;
+        assert!(game.can_swap());
+
+        game.board.set_cell(&Cell::new(1, 0), BLOCKED);
+        assert!(!game.can_swap());
+        game.swap_rule = SwapRule::SwapSides;
+        assert!(game.can_swap());
     }
 }
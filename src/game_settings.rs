@@ -0,0 +1,94 @@
+use hex_rules::{Board, SwapRule, Topology};
+
+use crate::errors::{ensure, HexError};
+use crate::utils::{
+    DEFAULT_FIELD_SIZE, DEFAULT_MOVE_TIMEOUT, DEFAULT_PLAYTIME, MAX_BID, MAX_MOVE_TIMEOUT,
+    MAX_PLAYTIME, MIN_BID, MIN_MOVE_TIMEOUT, MIN_PLAYTIME,
+};
+use crate::*;
+
+/// Settings a game or a challenge is created with, as passed by the creator.
+/// Missing settings get their defaults in `GameSettings::new`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde", default)]
+pub struct GameOptions {
+    pub field_size: Option<usize>,
+    pub field_height: Option<usize>,
+    pub bid: Option<U128>,
+    pub playtime: Option<u32>,
+    pub move_timeout: Option<BlockHeight>,
+    pub allow_takebacks: Option<bool>,
+    pub swap_rule: Option<SwapRule>,
+    pub misere: Option<bool>,
+    pub topology: Option<Topology>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GameSettings {
+    pub field_size: usize,
+    pub field_height: usize,
+    pub bid: Option<U128>,
+    pub playtime: Option<u32>,
+    pub move_timeout: Option<BlockHeight>,
+    pub allow_takebacks: bool,
+    pub swap_rule: SwapRule,
+    pub misere: bool,
+    pub topology: Topology,
+}
+
+impl GameSettings {
+    pub fn new(options: GameOptions) -> Result<Self, HexError> {
+        let GameOptions {
+            field_size,
+            field_height,
+            bid,
+            playtime,
+            move_timeout,
+            allow_takebacks,
+            swap_rule,
+            misere,
+            topology,
+        } = options;
+        let field_size = field_size.unwrap_or(DEFAULT_FIELD_SIZE);
+        let field_height = field_height.unwrap_or(field_size);
+        let topology = topology.unwrap_or_default();
+        let board = Board::with_topology(topology, field_size, field_height)?;
+        let swap_rule = swap_rule.unwrap_or_else(|| SwapRule::default_for(&board));
+        ensure(swap_rule.is_supported_by(&board), HexError::InvalidSwapRule)?;
+        if let Some(playtime) = playtime {
+            ensure(
+                (MIN_PLAYTIME..=MAX_PLAYTIME).contains(&playtime),
+                HexError::InvalidPlaytime,
+            )?;
+            ensure(bid.is_some(), HexError::PlaytimeWithoutBid)?;
+        }
+        if let Some(bid) = bid {
+            ensure((MIN_BID..=MAX_BID).contains(&bid.0), HexError::InvalidBid)?;
+        }
+        if let Some(move_timeout) = move_timeout {
+            ensure(
+                (MIN_MOVE_TIMEOUT..=MAX_MOVE_TIMEOUT).contains(&move_timeout),
+                HexError::InvalidMoveTimeout,
+            )?;
+            ensure(bid.is_none(), HexError::MoveTimeoutWithBid)?;
+        }
+
+        let (playtime, move_timeout) = if bid.is_some() {
+            (Some(playtime.unwrap_or(DEFAULT_PLAYTIME)), None)
+        } else {
+            (None, Some(move_timeout.unwrap_or(DEFAULT_MOVE_TIMEOUT)))
+        };
+        Ok(Self {
+            field_size,
+            field_height,
+            bid,
+            playtime,
+            move_timeout,
+            allow_takebacks: allow_takebacks.unwrap_or(bid.is_none()),
+            swap_rule,
+            misere: misere.unwrap_or(false),
+            topology,
+        })
+    }
+}
@@ -0,0 +1,117 @@
+use hex_rules::BLOCKED;
+
+use crate::*;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum CellState {
+    Empty,
+    Red,
+    Blue,
+    Blocked,
+}
+
+impl From<u8> for CellState {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => CellState::Empty,
+            1 => CellState::Red,
+            2 => CellState::Blue,
+            BLOCKED => CellState::Blocked,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// State of a game in a form that doesn't require clients to unpack the
+/// board. `board[y][x]` is the stone on `Cell::new(x, y)`, in the Game of Y
+/// every row is one cell shorter than the previous one.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GameView {
+    pub first_player: AccountId,
+    pub second_player: AccountId,
+    pub field_size: usize,
+    pub field_height: usize,
+    pub topology: Topology,
+    pub board: Vec<Vec<CellState>>,
+    pub turn: usize,
+    pub status: GameStatus,
+    pub player_to_move: Option<AccountId>,
+    pub color_to_move: Option<CellState>,
+    pub is_swap_legal: bool,
+    pub misere: bool,
+    pub empty_cells: Vec<Cell>,
+    pub last_move: Option<MoveRecord>,
+    pub winner: Option<Player>,
+}
+
+impl GameView {
+    pub fn new(game: &Game, last_move: Option<MoveRecord>) -> Self {
+        let board = &game.board;
+        let is_active = game.status() == GameStatus::Active;
+        Self {
+            first_player: game.first_player.clone(),
+            second_player: game.second_player.clone(),
+            field_size: board.width,
+            field_height: board.height,
+            topology: board.topology,
+            board: (0..board.height)
+                .map(|y| {
+                    (0..board.width)
+                        .map(|x| Cell::new(x, y))
+                        .filter(|cell| board.contains(cell))
+                        .map(|cell| board.get_cell(&cell).into())
+                        .collect()
+                })
+                .collect(),
+            turn: game.turn,
+            status: game.status(),
+            player_to_move: is_active.then(|| game.player_to_move().clone()),
+            color_to_move: is_active.then(|| game.color_to_move().into()),
+            is_swap_legal: is_active && game.can_swap(),
+            misere: game.misere,
+            empty_cells: board
+                .cells()
+                .filter(|cell| board.get_cell(cell) == 0)
+                .collect(),
+            last_move,
+            winner: game.winner.clone(),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod game_view_tests {
+    use near_sdk::test_utils::accounts;
+
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let mut game_with_data = GameWithData::new(accounts(0), accounts(1), 3, 2, None, None);
+        game_with_data.game.is_started = true;
+        game_with_data
+            .apply_move(MoveType::PLACE, Some(Cell::new(2, 1)))
+            .unwrap();
+
+        let view = GameView::new(&game_with_data.game, None);
+        assert_eq!(
+            view.board,
+            vec![
+                vec![CellState::Empty; 3],
+                vec![CellState::Empty, CellState::Empty, CellState::Red],
+            ]
+        );
+        assert_eq!(view.player_to_move, Some(accounts(1)));
+        assert_eq!(view.color_to_move, Some(CellState::Blue));
+        assert!(view.is_swap_legal);
+        assert_eq!(view.empty_cells.len(), 5);
+        assert!(!view.empty_cells.contains(&Cell::new(2, 1)));
+
+        game_with_data.game.is_finished = true;
+        let view = GameView::new(&game_with_data.game, None);
+        assert!(view.player_to_move.is_none());
+        assert!(!view.is_swap_legal);
+    }
+}
@@ -1,16 +1,18 @@
+use hex_rules::{Board, Cell, DisjointSet, Position, RulesError, SwapRule, Topology, BLOCKED};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::AccountId;
-use std::collections::VecDeque;
+use near_sdk::{AccountId, BlockHeight};
 
-use crate::board::Board;
-use crate::cell::Cell;
-use crate::game::Game;
+use crate::errors::{ensure, HexError};
+use crate::game::{Game, Player};
+use crate::initial_position::InitialPosition;
 use crate::*;
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct GameWithData {
     pub game: Game,
-    pub data: Board,
+    /// Groups of connected stones, see `Position::connections`.
+    pub connections: DisjointSet,
+    pub winning_path: Vec<Cell>,
 }
 
 impl GameWithData {
@@ -18,108 +20,191 @@ impl GameWithData {
         first_player: AccountId,
         second_player: AccountId,
         field_size: usize,
+        field_height: usize,
         playtime: Option<u32>,
+        move_timeout: Option<BlockHeight>,
     ) -> Self {
+        let game = Game::new(
+            first_player,
+            second_player,
+            field_size,
+            field_height,
+            playtime,
+            move_timeout,
+        );
+        let connections = Position::from_board(game.board.clone(), 0, false).connections;
         Self {
-            game: Game::new(first_player, second_player, field_size, playtime),
-            data: Board::new(field_size),
+            game,
+            connections,
+            winning_path: Vec::new(),
         }
     }
 
-    pub fn make_move(&mut self, move_type: MoveType, cell: Option<Cell>) {
-        match (move_type, cell) {
-            (MoveType::PLACE, Some(cell)) => {
-                if self.game.turn % 2 == 0 {
-                    self.game.place_counter(&cell, 1);
-                } else {
-                    self.game.place_counter(&cell, 2);
-                }
-                self.process_cell(cell);
-            }
-            (MoveType::SWAP, _) => {
-                let cell = self.game.swap_rule();
-                self.data.set_cell(&cell, 0);
-                self.process_cell(cell.symm());
-            }
-            _ => unreachable!(),
+    /// Places the stones of the initial position on the empty board and lets
+    /// the chosen side move first. Such games can't have a swap rule.
+    pub fn set_initial_position(
+        &mut self,
+        initial_position: InitialPosition,
+    ) -> Result<(), HexError> {
+        ensure(
+            self.game.swap_rule == SwapRule::NoSwap,
+            HexError::SwapWithInitialPosition,
+        )?;
+        let mut board = self.game.board.clone();
+        for (cell, color) in initial_position.stones() {
+            ensure(board.contains(cell), RulesError::OutOfBounds.into())?;
+            ensure(board.get_cell(cell) == 0, RulesError::CellFilled.into())?;
+            board.set_cell(cell, color);
+        }
+        let turn = initial_position.first_turn();
+        let mut position = Position::from_board(board, turn, false);
+        ensure(
+            position.winner().is_none(),
+            HexError::InvalidInitialPosition,
+        )?;
+        self.game.board = position.board;
+        self.game.turn = turn;
+        self.game.initial_position = Some(initial_position);
+        self.connections = position.connections;
+        Ok(())
+    }
+
+    /// Replaces the empty board with one of the given topology, e.g. to play
+    /// the Game of Y.
+    pub fn set_topology(&mut self, topology: Topology) -> Result<(), HexError> {
+        let board = &self.game.board;
+        let board = Board::with_topology(topology, board.width, board.height)?;
+        let position = Position::from_board(board, self.game.turn, false);
+        self.game.board = position.board;
+        self.connections = position.connections;
+        Ok(())
+    }
+
+    /// Blocks the cells, so that neither player can place a stone on them.
+    pub fn add_holes(&mut self, holes: &[Cell]) -> Result<(), HexError> {
+        let board = &mut self.game.board;
+        ensure(
+            holes.len() <= board.cell_count() / 2,
+            HexError::InvalidHoles,
+        )?;
+        for cell in holes {
+            ensure(board.contains(cell), RulesError::OutOfBounds.into())?;
+            ensure(board.get_cell(cell) == 0, RulesError::CellFilled.into())?;
+            board.set_cell(cell, BLOCKED);
+        }
+        Ok(())
+    }
+
+    /// The position as seen by the rules engine.
+    pub fn position(&self) -> Position {
+        Position {
+            board: self.game.board.clone(),
+            turn: self.game.turn,
+            sides_swapped: self.game.sides_swapped,
+            connections: self.connections.clone(),
+        }
+    }
+
+    /// Runs the rules engine on the position and stores the result, even if
+    /// the engine rejects the operation.
+    fn update_position<T>(
+        &mut self,
+        operation: impl FnOnce(&mut Position) -> Result<T, RulesError>,
+    ) -> Result<T, RulesError> {
+        let mut position = Position {
+            board: std::mem::take(&mut self.game.board),
+            turn: self.game.turn,
+            sides_swapped: self.game.sides_swapped,
+            connections: std::mem::take(&mut self.connections),
         };
+        let result = operation(&mut position);
+        self.game.board = position.board;
+        self.game.turn = position.turn;
+        self.game.sides_swapped = position.sides_swapped;
+        self.connections = position.connections;
+        result
+    }
+
+    /// Applies the move on behalf of the given player and returns the cell
+    /// where the stone ends up.
+    pub fn make_move(
+        &mut self,
+        account_id: &AccountId,
+        move_type: MoveType,
+        cell: Option<Cell>,
+    ) -> Result<Cell, HexError> {
+        ensure(
+            account_id == self.game.player_to_move(),
+            HexError::NotYourTurn,
+        )?;
+        self.apply_move(move_type, cell)
     }
 
-    fn process_cell(&mut self, cell: Cell) {
-        let color = self.game.board.get_cell(&cell);
-        let (mut border1, mut border2) = if color == 1 {
-            (cell.y == 0, cell.y + 1 == self.data.size)
-        } else {
-            (cell.x == 0, cell.x + 1 == self.data.size)
+    /// Applies the move for the player whose turn it is. If the move finishes
+    /// the game, the player wins, or loses in a misère game. If the board
+    /// fills up without a connection, which holes make possible, it's a draw.
+    pub fn apply_move(
+        &mut self,
+        move_type: MoveType,
+        cell: Option<Cell>,
+    ) -> Result<Cell, HexError> {
+        let placed = match (move_type, cell) {
+            (MoveType::PLACE, Some(cell)) => Some(cell),
+            (MoveType::PLACE, None) => return Err(HexError::InvalidMoveArgs),
+            (MoveType::SWAP, _) => None,
         };
-        let neighbours = cell.get_neighbours(self.data.size);
-        let good_neighbours = neighbours
-            .iter()
-            .filter(|c| self.game.board.get_cell(c) == color);
-        border1 = border1 || good_neighbours.clone().any(|c| self.data.get_cell(c) == 1);
-        border2 = border2 || good_neighbours.clone().any(|c| self.data.get_cell(c) == 2);
-        if border1 && border2 {
-            self.game.is_finished = true;
-        } else if border1 {
-            self.bfs(cell, color, 1);
-        } else if border2 {
-            self.bfs(cell, color, 2);
+        let swap_rule = self.game.swap_rule;
+        let (cell, winning_path, is_draw) = self.update_position(|position| {
+            let cell = match placed {
+                Some(cell) => {
+                    position.place(&cell)?;
+                    cell
+                }
+                None => position.swap(swap_rule)?,
+            };
+            let winning_path = position.winner().map(|color| position.winning_path(color));
+            Ok((cell, winning_path, position.is_draw()))
+        })?;
+        self.game.update_block_height();
+        self.game.takeback_requested_by = None;
+        self.game.commitment = None;
+        if let Some(winning_path) = winning_path {
+            self.winning_path = winning_path;
+            let winner = if (self.game.turn % 2 == 1) != self.game.misere {
+                Player::First
+            } else {
+                Player::Second
+            };
+            self.game.finish(Some(winner), FinishReason::Connection);
+        } else if is_draw {
+            self.game.finish(None, FinishReason::Draw);
         }
+        Ok(cell)
     }
 
-    fn bfs(&mut self, cell: Cell, color: u8, border: u8) {
-        self.data.set_cell(&cell, border);
-        let mut q: VecDeque<Cell> = VecDeque::new();
-        q.push_back(cell);
-        let field_size = self.data.size;
-        while !q.is_empty() {
-            let v = q.pop_front().unwrap();
-            let neighbours = v.get_neighbours(field_size);
-            let good_neighbours: Vec<Cell> = neighbours
-                .into_iter()
-                .filter(|c| self.game.board.get_cell(c) == color && self.data.get_cell(c) != border)
-                .collect();
-            if good_neighbours
-                .clone()
-                .into_iter()
-                .any(|c| self.data.get_cell(&c) != 0)
-            {
-                self.game.is_finished = true;
-                return;
-            }
-            for c in good_neighbours.into_iter() {
-                self.data.set_cell(&c, border);
-                q.push_back(Cell::new(c.x, c.y));
-            }
-        }
+    /// Reverts the last move, which is described by the given record.
+    pub fn undo_move(&mut self, record: &MoveRecord) -> Result<(), RulesError> {
+        self.update_position(|position| match record.move_type {
+            MoveType::PLACE => position.undo_place(&record.cell),
+            MoveType::SWAP => position.undo_swap(&record.cell),
+        })?;
+        self.game.takeback_requested_by = None;
+        self.game.commitment = None;
+        self.game.current_block_height = env::block_height();
+        Ok(())
     }
 }
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod game_with_board_tests {
-    use std::fmt::Debug;
-
     use near_sdk::{
         test_utils::{accounts, VMContextBuilder},
         testing_env,
     };
 
-    use super::*;
-
-    impl PartialEq for Board {
-        fn eq(&self, other: &Self) -> bool {
-            self.size == other.size && self.field == other.field
-        }
-    }
+    use hex_rules::Board;
 
-    impl Debug for Board {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            f.debug_struct("Board")
-                .field("size", &self.size)
-                .field("field", &self.field)
-                .finish()
-        }
-    }
+    use super::*;
 
     fn get_context(account: AccountId) -> near_sdk::VMContext {
         VMContextBuilder::new()
@@ -127,131 +212,366 @@ mod game_with_board_tests {
             .build()
     }
 
-    #[test]
-    fn test_bfs() {
-        let mut test_game = GameWithData::new(accounts(0), accounts(1), 5, None);
-        test_game.game.board.set_cell(&Cell::new(0, 0), 1);
-        test_game.game.board.set_cell(&Cell::new(0, 1), 1);
-        test_game.game.board.set_cell(&Cell::new(0, 2), 1);
-
-        test_game.game.board.set_cell(&Cell::new(4, 4), 1);
-        test_game.game.board.set_cell(&Cell::new(3, 4), 1);
-
-        test_game.game.board.set_cell(&Cell::new(0, 3), 1);
-        test_game.game.board.set_cell(&Cell::new(1, 2), 1);
-
-        test_game.game.board.set_cell(&Cell::new(4, 0), 2);
-
-        test_game.game.board.set_cell(&Cell::new(2, 1), 1);
-
-        test_game.game.board.set_cell(&Cell::new(3, 0), 2);
-
-        let mut test_data = Board::new(5);
-        test_data.set_cell(&Cell::new(0, 0), 2);
-        test_data.set_cell(&Cell::new(0, 1), 2);
-        test_data.set_cell(&Cell::new(0, 2), 2);
-        test_data.set_cell(&Cell::new(0, 3), 2);
-        test_data.set_cell(&Cell::new(1, 2), 2);
-        test_data.set_cell(&Cell::new(2, 1), 2);
+    fn index(test_game: &GameWithData, cell: &Cell) -> usize {
+        cell.y * test_game.game.board.width + cell.x
+    }
 
-        test_game.bfs(Cell::new(0, 2), 1, 2);
-        assert_eq!(test_game.data, test_data);
+    fn connected(test_game: &mut GameWithData, a: &Cell, b: &Cell) -> bool {
+        let (a, b) = (index(test_game, a), index(test_game, b));
+        test_game.connections.connected(a, b)
+    }
 
-        test_data.set_cell(&Cell::new(4, 0), 2);
-        test_data.set_cell(&Cell::new(3, 0), 2);
+    fn on_border(test_game: &mut GameWithData, cell: &Cell, color: u8, border: usize) -> bool {
+        let (width, height) = (test_game.game.board.width, test_game.game.board.height);
+        let border = width * height + 2 * (color as usize - 1) + border - 1;
+        let cell = index(test_game, cell);
+        test_game.connections.connected(cell, border)
+    }
 
-        test_game.bfs(Cell::new(3, 0), 2, 2);
-        assert_eq!(test_game.data, test_data);
+    fn same_groups(a: &mut GameWithData, b: &mut GameWithData) -> bool {
+        let nodes = a.game.board.width * a.game.board.height + 4;
+        (0..nodes).all(|i| {
+            (0..nodes).all(|j| a.connections.connected(i, j) == b.connections.connected(i, j))
+        })
     }
 
     #[test]
-    fn test_process_cell() {
-        let mut test_game = GameWithData::new(accounts(0), accounts(1), 5, None);
-        test_game.game.board.set_cell(&Cell::new(0, 0), 1);
-        test_game.game.board.set_cell(&Cell::new(0, 1), 1);
-        test_game.game.board.set_cell(&Cell::new(0, 2), 1);
+    fn test_apply_move() {
+        testing_env!(get_context(accounts(0)));
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 11, 11, None, None);
+        for (x, y) in [(1, 1), (1, 2), (10, 7), (5, 9), (3, 7)] {
+            test_game
+                .apply_move(MoveType::PLACE, Some(Cell::new(x, y)))
+                .unwrap();
+        }
 
-        test_game.game.board.set_cell(&Cell::new(4, 4), 1);
-        test_game.game.board.set_cell(&Cell::new(3, 4), 1);
+        assert_eq!(test_game.game.turn, 5);
+        for i in 0..11 {
+            for j in 0..11 {
+                let cell = Cell::new(i, j);
+                if cell == Cell::new(1, 1) || cell == Cell::new(10, 7) || cell == Cell::new(3, 7) {
+                    assert_eq!(test_game.game.board.get_cell(&cell), 1);
+                } else if cell == Cell::new(1, 2) || cell == Cell::new(5, 9) {
+                    assert_eq!(test_game.game.board.get_cell(&cell), 2);
+                } else {
+                    assert_eq!(test_game.game.board.get_cell(&cell), 0);
+                }
+            }
+        }
+    }
 
-        test_game.game.board.set_cell(&Cell::new(0, 3), 1);
-        test_game.game.board.set_cell(&Cell::new(1, 2), 1);
+    #[test]
+    fn test_apply_move_cell_is_already_filled() {
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 11, 11, None, None);
+        test_game
+            .apply_move(MoveType::PLACE, Some(Cell::new(1, 1)))
+            .unwrap();
+        assert_eq!(
+            test_game.apply_move(MoveType::PLACE, Some(Cell::new(1, 1))),
+            Err(RulesError::CellFilled.into())
+        );
+    }
 
-        test_game.game.board.set_cell(&Cell::new(4, 0), 2);
+    #[test]
+    fn test_add_holes() {
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 3, 2, None, None);
+        assert_eq!(
+            test_game.add_holes(&[
+                Cell::new(0, 0),
+                Cell::new(1, 0),
+                Cell::new(2, 0),
+                Cell::new(0, 1)
+            ]),
+            Err(HexError::InvalidHoles)
+        );
+        assert_eq!(
+            test_game.add_holes(&[Cell::new(0, 2)]),
+            Err(HexError::Rules(RulesError::OutOfBounds))
+        );
+        test_game.add_holes(&[Cell::new(1, 0)]).unwrap();
+        assert_eq!(test_game.game.board.get_cell(&Cell::new(1, 0)), BLOCKED);
+        assert_eq!(
+            test_game.add_holes(&[Cell::new(1, 0)]),
+            Err(HexError::Rules(RulesError::CellFilled))
+        );
+        test_game.game.is_started = true;
+        assert_eq!(
+            test_game.apply_move(MoveType::PLACE, Some(Cell::new(1, 0))),
+            Err(RulesError::CellBlocked.into())
+        );
+    }
 
-        test_game.game.board.set_cell(&Cell::new(2, 1), 1);
+    #[test]
+    fn test_set_initial_position() {
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 3, 3, None, None);
+        let initial_position = |red: Vec<Cell>, blue: Vec<Cell>| InitialPosition {
+            red,
+            blue,
+            blue_moves_first: true,
+        };
+        assert_eq!(
+            test_game.set_initial_position(initial_position(vec![], vec![])),
+            Err(HexError::SwapWithInitialPosition)
+        );
+        test_game.game.swap_rule = SwapRule::NoSwap;
+        assert_eq!(
+            test_game.set_initial_position(initial_position(vec![Cell::new(3, 0)], vec![])),
+            Err(HexError::Rules(RulesError::OutOfBounds))
+        );
+        assert_eq!(
+            test_game.set_initial_position(initial_position(
+                vec![Cell::new(1, 1)],
+                vec![Cell::new(1, 1)]
+            )),
+            Err(HexError::Rules(RulesError::CellFilled))
+        );
+        let column = vec![Cell::new(0, 0), Cell::new(0, 1), Cell::new(0, 2)];
+        assert_eq!(
+            test_game.set_initial_position(initial_position(column, vec![])),
+            Err(HexError::InvalidInitialPosition)
+        );
+        assert_eq!(test_game.game.board, Board::new(3, 3).unwrap());
+
+        test_game
+            .set_initial_position(initial_position(
+                vec![Cell::new(1, 0), Cell::new(1, 1)],
+                vec![Cell::new(0, 2)],
+            ))
+            .unwrap();
+        assert_eq!(test_game.game.turn, 1);
+        assert_eq!(test_game.game.color_to_move(), 2);
+        assert_eq!(test_game.game.board.get_cell(&Cell::new(0, 2)), 2);
+        assert_eq!(
+            test_game.apply_move(MoveType::SWAP, None),
+            Err(RulesError::SwapDisabled.into())
+        );
+        test_game
+            .apply_move(MoveType::PLACE, Some(Cell::new(0, 0)))
+            .unwrap();
+        test_game
+            .apply_move(MoveType::PLACE, Some(Cell::new(1, 2)))
+            .unwrap();
+        assert!(test_game.game.is_finished);
+        assert!(matches!(test_game.game.winner, Some(Player::First)));
+    }
 
-        test_game.game.board.set_cell(&Cell::new(3, 0), 2);
+    #[test]
+    fn test_misere() {
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 2, 2, None, None);
+        test_game.game.misere = true;
+        for cell in [Cell::new(0, 0), Cell::new(1, 0), Cell::new(0, 1)] {
+            test_game.apply_move(MoveType::PLACE, Some(cell)).unwrap();
+        }
+        assert!(test_game.game.is_finished);
+        assert!(matches!(test_game.game.winner, Some(Player::Second)));
+        assert_eq!(
+            test_game.winning_path,
+            vec![Cell::new(0, 0), Cell::new(0, 1)]
+        );
+    }
 
-        let mut test_data = Board::new(5);
-        test_data.set_cell(&Cell::new(0, 0), 1);
-        test_data.set_cell(&Cell::new(0, 1), 1);
-        test_data.set_cell(&Cell::new(0, 2), 1);
-        test_data.set_cell(&Cell::new(0, 3), 1);
-        test_data.set_cell(&Cell::new(1, 2), 1);
-        test_data.set_cell(&Cell::new(2, 1), 1);
+    #[test]
+    fn test_apply_move_rejected() {
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 3, 3, None, None);
+        assert_eq!(
+            test_game.apply_move(MoveType::PLACE, Some(Cell::new(3, 0))),
+            Err(RulesError::OutOfBounds.into())
+        );
+        assert_eq!(
+            test_game.apply_move(MoveType::SWAP, None),
+            Err(RulesError::SwapNotAllowed.into())
+        );
+        assert_eq!(test_game.game.board, Board::new(3, 3).unwrap());
+        assert_eq!(test_game.game.turn, 0);
+    }
 
-        test_game.process_cell(Cell::new(0, 1));
-        assert_eq!(test_game.data, Board::new(5));
+    #[test]
+    fn test_swap_too_late() {
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 11, 11, None, None);
+        test_game
+            .apply_move(MoveType::PLACE, Some(Cell::new(2, 5)))
+            .unwrap();
+        test_game
+            .apply_move(MoveType::PLACE, Some(Cell::new(10, 7)))
+            .unwrap();
+        assert_eq!(
+            test_game.apply_move(MoveType::SWAP, None),
+            Err(RulesError::SwapNotAllowed.into())
+        );
+    }
 
-        test_game.process_cell(Cell::new(0, 0));
-        assert_eq!(test_game.data, test_data);
+    #[test]
+    fn test_swap() {
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 11, 11, None, None);
+        test_game
+            .apply_move(MoveType::PLACE, Some(Cell::new(10, 7)))
+            .unwrap();
+
+        let c = test_game.apply_move(MoveType::SWAP, None).unwrap();
+        assert_eq!(Cell::new(7, 10), c);
+        assert_eq!(0, test_game.game.board.get_cell(&Cell::new(10, 7)));
+        assert_eq!(2, test_game.game.board.get_cell(&Cell::new(7, 10)));
+
+        for (x, y) in [(1, 1), (5, 9), (3, 7)] {
+            test_game
+                .apply_move(MoveType::PLACE, Some(Cell::new(x, y)))
+                .unwrap();
+        }
+        assert_eq!(test_game.game.turn, 5);
+        assert_eq!(test_game.game.board.get_cell(&Cell::new(5, 9)), 2);
+        assert_eq!(test_game.game.board.get_cell(&Cell::new(3, 7)), 1);
     }
 
     #[test]
     fn test_make_move() {
-        let mut test_game = GameWithData::new(accounts(0), accounts(1), 5, None);
-        let mut test_data = Board::new(5);
-        assert_eq!(test_game.data, test_data);
-
-        testing_env!(get_context(accounts(0)));
-        test_game.make_move(MoveType::PLACE, Some(Cell::new(3, 0)));
-        test_data.set_cell(&Cell::new(3, 0), 1);
-        assert_eq!(test_game.data, test_data);
-
-        testing_env!(get_context(accounts(1)));
-        test_game.make_move(MoveType::SWAP, None);
-        test_data.set_cell(&Cell::new(3, 0), 0);
-        test_data.set_cell(&Cell::new(0, 3), 1);
-        assert_eq!(test_game.data, test_data);
-
-        testing_env!(get_context(accounts(0)));
-        test_game.make_move(MoveType::PLACE, Some(Cell::new(4, 4)));
-        test_data.set_cell(&Cell::new(4, 4), 2);
-        assert_eq!(test_game.data, test_data);
-
-        testing_env!(get_context(accounts(1)));
-        test_game.make_move(MoveType::PLACE, Some(Cell::new(1, 2)));
-        test_data.set_cell(&Cell::new(1, 2), 1);
-        assert_eq!(test_game.data, test_data);
-
-        testing_env!(get_context(accounts(0)));
-        test_game.make_move(MoveType::PLACE, Some(Cell::new(4, 2)));
-        assert_eq!(test_game.data, test_data);
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 5, 5, None, None);
+
+        test_game
+            .make_move(&accounts(0), MoveType::PLACE, Some(Cell::new(3, 0)))
+            .unwrap();
+        assert!(on_border(&mut test_game, &Cell::new(3, 0), 1, 1));
+
+        test_game
+            .make_move(&accounts(1), MoveType::SWAP, None)
+            .unwrap();
+        assert!(!on_border(&mut test_game, &Cell::new(3, 0), 1, 1));
+        assert!(on_border(&mut test_game, &Cell::new(0, 3), 2, 1));
+
+        test_game
+            .make_move(&accounts(0), MoveType::PLACE, Some(Cell::new(4, 4)))
+            .unwrap();
+        assert!(on_border(&mut test_game, &Cell::new(4, 4), 1, 2));
+
+        test_game
+            .make_move(&accounts(1), MoveType::PLACE, Some(Cell::new(1, 2)))
+            .unwrap();
+        assert!(connected(
+            &mut test_game,
+            &Cell::new(0, 3),
+            &Cell::new(1, 2)
+        ));
+
+        test_game
+            .make_move(&accounts(0), MoveType::PLACE, Some(Cell::new(4, 2)))
+            .unwrap();
+        assert!(!on_border(&mut test_game, &Cell::new(4, 2), 1, 2));
+
+        test_game
+            .make_move(&accounts(1), MoveType::PLACE, Some(Cell::new(3, 2)))
+            .unwrap();
+        assert!(!connected(
+            &mut test_game,
+            &Cell::new(3, 2),
+            &Cell::new(1, 2)
+        ));
+
+        test_game
+            .make_move(&accounts(0), MoveType::PLACE, Some(Cell::new(4, 3)))
+            .unwrap();
+        assert!(on_border(&mut test_game, &Cell::new(4, 2), 1, 2));
+        assert!(connected(
+            &mut test_game,
+            &Cell::new(4, 2),
+            &Cell::new(4, 4)
+        ));
+        assert!(!test_game.game.is_finished);
+    }
 
-        testing_env!(get_context(accounts(1)));
-        test_game.make_move(MoveType::PLACE, Some(Cell::new(3, 2)));
-        assert_eq!(test_game.data, test_data);
+    #[test]
+    fn test_undo_move() {
+        let moves = [
+            (MoveType::PLACE, Some(Cell::new(3, 0))),
+            (MoveType::SWAP, None),
+            (MoveType::PLACE, Some(Cell::new(1, 2))),
+            (MoveType::PLACE, Some(Cell::new(0, 2))),
+            (MoveType::PLACE, Some(Cell::new(2, 1))),
+        ];
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 5, 5, None, None);
+        let mut records = Vec::new();
+        for (move_type, cell) in moves.iter().cloned() {
+            let cell = test_game.apply_move(move_type.clone(), cell).unwrap();
+            records.push(MoveRecord::new(move_type, cell));
+        }
 
-        testing_env!(get_context(accounts(0)));
-        test_game.make_move(MoveType::PLACE, Some(Cell::new(4, 3)));
-        test_data.set_cell(&Cell::new(4, 2), 2);
-        test_data.set_cell(&Cell::new(4, 3), 2);
-        assert_eq!(test_game.data, test_data);
+        for played in (0..moves.len()).rev() {
+            test_game.undo_move(&records[played]).unwrap();
+            let mut expected_game = GameWithData::new(accounts(0), accounts(1), 5, 5, None, None);
+            for (move_type, cell) in moves[..played].iter().cloned() {
+                expected_game.apply_move(move_type, cell).unwrap();
+            }
+            assert_eq!(test_game.game.board, expected_game.game.board);
+            assert!(same_groups(&mut test_game, &mut expected_game));
+            assert_eq!(test_game.game.turn, played);
+        }
     }
 
     #[test]
-    #[should_panic]
     fn test_make_move_incorrect_args() {
-        let mut test_game = GameWithData::new(accounts(0), accounts(1), 5, None);
-        test_game.make_move(MoveType::PLACE, None);
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 5, 5, None, None);
+        assert_eq!(
+            test_game.make_move(&accounts(0), MoveType::PLACE, None),
+            Err(HexError::InvalidMoveArgs)
+        );
+        assert_eq!(test_game.game.turn, 0);
     }
 
     #[test]
-    #[should_panic]
     fn test_make_move_wrong_player() {
-        let mut test_game = GameWithData::new(accounts(0), accounts(1), 5, None);
-        testing_env!(get_context(accounts(1)));
-        test_game.make_move(MoveType::PLACE, Some(Cell::new(0, 0)));
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 5, 5, None, None);
+        assert_eq!(
+            test_game.make_move(&accounts(1), MoveType::PLACE, Some(Cell::new(0, 0))),
+            Err(HexError::NotYourTurn)
+        );
+        assert_eq!(
+            test_game.make_move(
+                &env::current_account_id(),
+                MoveType::PLACE,
+                Some(Cell::new(0, 0))
+            ),
+            Err(HexError::NotYourTurn)
+        );
+    }
+
+    #[test]
+    fn test_swap_sides() {
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 3, 2, None, None);
+        test_game
+            .apply_move(MoveType::PLACE, Some(Cell::new(0, 0)))
+            .unwrap();
+        let cell = test_game.apply_move(MoveType::SWAP, None).unwrap();
+        assert_eq!(cell, Cell::new(0, 0));
+        assert!(test_game.game.sides_swapped);
+        assert_eq!(test_game.game.board.get_cell(&Cell::new(0, 0)), 1);
+        assert_eq!(test_game.game.color_to_move(), 2);
+
+        test_game
+            .apply_move(MoveType::PLACE, Some(Cell::new(2, 0)))
+            .unwrap();
+        assert_eq!(test_game.game.board.get_cell(&Cell::new(2, 0)), 2);
+        test_game
+            .apply_move(MoveType::PLACE, Some(Cell::new(0, 1)))
+            .unwrap();
+        assert!(test_game.game.is_finished);
+        assert!(matches!(test_game.game.winner, Some(Player::Second)));
+        assert_eq!(
+            test_game.winning_path,
+            vec![Cell::new(0, 0), Cell::new(0, 1)]
+        );
+    }
+
+    #[test]
+    fn test_undo_swap_sides() {
+        let mut test_game = GameWithData::new(accounts(0), accounts(1), 3, 2, None, None);
+        let cell = test_game
+            .apply_move(MoveType::PLACE, Some(Cell::new(1, 0)))
+            .unwrap();
+        test_game.apply_move(MoveType::SWAP, None).unwrap();
+        test_game
+            .undo_move(&MoveRecord::new(MoveType::SWAP, cell))
+            .unwrap();
+        assert!(!test_game.game.sides_swapped);
+        assert_eq!(test_game.game.turn, 1);
+        assert_eq!(test_game.game.board.get_cell(&Cell::new(1, 0)), 1);
+        assert_eq!(test_game.game.color_to_move(), 2);
     }
 }
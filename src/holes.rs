@@ -0,0 +1,54 @@
+use hex_rules::Board;
+
+use crate::*;
+
+/// Blocked cells of a "Hex with holes" game: either listed explicitly or
+/// chosen at random among all cells of the board.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Holes {
+    Cells(Vec<Cell>),
+    Random(u32),
+}
+
+impl Holes {
+    /// Random holes are drawn with `env::random_seed`, so both players can
+    /// check the draw, but neither of them can choose it.
+    pub fn cells(&self, board: &Board) -> Vec<Cell> {
+        match self {
+            Holes::Cells(cells) => cells.clone(),
+            Holes::Random(count) => {
+                let mut cells: Vec<Cell> = board.cells().collect();
+                let count = (*count as usize).min(cells.len());
+                let seed = env::random_seed();
+                (0..count)
+                    .map(|i| {
+                        let hash = env::sha256(&[&seed[..], &i.to_le_bytes()].concat());
+                        let mut bytes = [0u8; 8];
+                        bytes.copy_from_slice(&hash[..8]);
+                        cells.swap_remove(u64::from_le_bytes(bytes) as usize % cells.len())
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod holes_tests {
+    use super::*;
+
+    #[test]
+    fn test_random_cells() {
+        let board = Board::new(5, 4).unwrap();
+        let mut cells = Holes::Random(10).cells(&board);
+        assert_eq!(cells.len(), 10);
+        assert!(cells.iter().all(|cell| board.contains(cell)));
+        assert_eq!(cells, Holes::Random(10).cells(&board));
+
+        cells.sort_by_key(|cell| (cell.y, cell.x));
+        cells.dedup();
+        assert_eq!(cells.len(), 10);
+        assert_eq!(Holes::Random(30).cells(&board).len(), 20);
+    }
+}
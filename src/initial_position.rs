@@ -0,0 +1,54 @@
+use crate::*;
+
+/// Stones placed on the board before the first move, e.g. to give a handicap
+/// to the weaker player. Games started from such a position are played
+/// without the swap rule.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug, Default,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct InitialPosition {
+    #[serde(default)]
+    pub red: Vec<Cell>,
+    #[serde(default)]
+    pub blue: Vec<Cell>,
+    /// If set, the second player (blue) makes the first move.
+    #[serde(default)]
+    pub blue_moves_first: bool,
+}
+
+impl InitialPosition {
+    /// Cells of both colors together with the color placed on them.
+    pub fn stones(&self) -> impl Iterator<Item = (&Cell, u8)> {
+        self.red
+            .iter()
+            .map(|cell| (cell, 1))
+            .chain(self.blue.iter().map(|cell| (cell, 2)))
+    }
+
+    pub fn first_turn(&self) -> usize {
+        if self.blue_moves_first {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod initial_position_tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize() {
+        let position: InitialPosition =
+            near_sdk::serde_json::from_str(r#"{"red": ["a1", {"x": 1, "y": 1}]}"#).unwrap();
+        assert_eq!(position.red, vec![Cell::new(0, 0), Cell::new(1, 1)]);
+        assert!(position.blue.is_empty());
+        assert_eq!(position.first_turn(), 0);
+        assert_eq!(
+            position.stones().collect::<Vec<_>>(),
+            vec![(&Cell::new(0, 0), 1), (&Cell::new(1, 1), 1)]
+        );
+    }
+}
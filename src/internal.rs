@@ -1,4 +1,11 @@
-use crate::{game::Player, *};
+use crate::board::BoardLogs;
+use crate::{
+    errors::{ensure, HexError},
+    events::{FinishReason, HexEvent},
+    game::Player,
+    utils::promise_result_json,
+    *,
+};
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -9,6 +16,18 @@ pub enum FinishedStreams {
     Both,
 }
 
+/// Action to perform once the streams of a wagered game are paused.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum GameAction {
+    Move {
+        account_id: AccountId,
+        move_type: MoveType,
+        cell: Option<Cell>,
+    },
+    Takeback,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub enum StatusType {
@@ -45,103 +64,189 @@ impl StatusType {
 #[near_bindgen]
 impl Contract {
     #[private]
-    pub fn get_game_internal(&self, index: GameIndex) -> Game {
-        require!(env::predecessor_account_id() == env::current_account_id());
-        let game = self.games.get(&index).map(|x| x.game);
-        game.unwrap()
+    #[handle_result]
+    pub fn get_game_internal(&self, index: GameIndex) -> Result<Game, HexError> {
+        let game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
+        Ok(game_with_data.game)
     }
 
     #[private]
+    #[handle_result]
     pub fn make_move_internal(
         &mut self,
         index: GameIndex,
+        account_id: AccountId,
         move_type: MoveType,
         cell: Option<Cell>,
-    ) -> Promise {
-        require!(env::predecessor_account_id() == env::current_account_id());
-        let mut game_with_data = self.games.get(&index).expect("Game doesn't exist.");
+    ) -> Result<Promise, HexError> {
+        let mut game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
         if game_with_data.game.is_finished {
-            return Self::ext(env::current_account_id()).get_game_internal(index);
+            return Ok(Self::ext(env::current_account_id()).get_game_internal(index));
         }
         let old_board = game_with_data.game.board.clone();
+        let turn = game_with_data.game.turn;
+        let player = game_with_data.game.player_to_move().clone();
 
-        game_with_data.make_move(move_type, cell);
-
-        env::log_str("Old board:");
-        old_board.debug_logs();
+        let cell = game_with_data.make_move(&account_id, move_type.clone(), cell)?;
+        let swap_rule = game_with_data.game.swap_rule;
+        let mut record = MoveRecord::new(move_type.clone(), cell.clone());
+        if let MoveType::SWAP = move_type {
+            record.swap_rule = Some(swap_rule);
+        }
+        self.record_move(index, record);
+        let from = match swap_rule {
+            SwapRule::SwapPieces => cell.symm(),
+            _ => cell.clone(),
+        };
+        env::log_str(&match move_type {
+            MoveType::PLACE => format!("{} placed a stone on {}.", player, cell),
+            MoveType::SWAP if swap_rule == SwapRule::SwapSides => {
+                format!("{} swapped sides, taking the stone on {}.", player, cell)
+            }
+            MoveType::SWAP => format!("{} swapped the stone from {} to {}.", player, from, cell),
+        });
+        match move_type {
+            MoveType::PLACE => HexEvent::MoveMade {
+                game_id: index,
+                player,
+                move_type,
+                cell,
+                turn,
+            },
+            MoveType::SWAP => HexEvent::SwapApplied {
+                game_id: index,
+                player,
+                from,
+                to: cell,
+                rule: swap_rule,
+                turn,
+            },
+        }
+        .emit();
 
-        env::log_str("New board:");
-        game_with_data.game.board.debug_logs();
+        game_with_data.game.board.log_changes(&old_board);
 
         if game_with_data.game.is_finished {
-            if game_with_data.game.turn % 2 == 1 {
-                game_with_data.game.winner = Some(Player::First);
-                env::log_str("First player wins!");
-            } else {
-                game_with_data.game.winner = Some(Player::Second);
-                env::log_str("Second player wins!");
+            let reason = match game_with_data.game.winner {
+                Some(Player::First) => {
+                    env::log_str("First player wins!");
+                    FinishReason::Connection
+                }
+                Some(Player::Second) => {
+                    env::log_str("Second player wins!");
+                    FinishReason::Connection
+                }
+                None => {
+                    env::log_str("The board is full, the game ends in a draw.");
+                    FinishReason::Draw
+                }
+            };
+            if reason == FinishReason::Connection {
+                let winning_path: Vec<String> = game_with_data
+                    .winning_path
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                let label = if game_with_data.game.misere {
+                    "Losing path"
+                } else {
+                    "Winning path"
+                };
+                env::log_str(&format!("{}: {}.", label, winning_path.join(" ")));
             }
             self.games.insert(&index, &game_with_data);
+            HexEvent::game_finished(index, &game_with_data.game, reason).emit();
+            self.release_history_storage(index);
             let winner = game_with_data.game.winner.clone();
-            if let Some(bid) = self.bids.get(&index) {
+            Ok(if let Some(bid) = self.bids.get(&index) {
+                let payout = match winner {
+                    Some(winner) => self.player_won(index, &bid, &game_with_data.game, winner),
+                    None => self.refund_bids(index, &bid, &game_with_data.game),
+                };
                 bid.stop_streams()
-                    .then(self.player_won(&bid, &game_with_data.game, winner.unwrap()))
+                    .then(payout)
                     .then(Self::ext(env::current_account_id()).get_game_internal(index))
             } else {
                 Self::ext(env::current_account_id()).get_game_internal(index)
-            }
+            })
         } else {
             self.games.insert(&index, &game_with_data);
-            if let Some(bid) = self.bids.get(&index) {
-                if game_with_data.game.turn % 2 == 1 {
-                    start_stream(bid.stream_to_second_player)
-                        .then(Self::ext(env::current_account_id()).get_game_internal(index))
-                } else {
-                    start_stream(bid.stream_to_first_player)
-                        .then(Self::ext(env::current_account_id()).get_game_internal(index))
-                }
-            } else {
-                Self::ext(env::current_account_id()).get_game_internal(index)
-            }
+            Ok(self.start_player_stream(index, &game_with_data.game))
+        }
+    }
+
+    #[private]
+    #[handle_result]
+    pub fn takeback_internal(&mut self, index: GameIndex) -> Result<Promise, HexError> {
+        let mut game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
+        if game_with_data.game.is_finished || game_with_data.game.takeback_requested_by.is_none() {
+            return Ok(Self::ext(env::current_account_id()).get_game_internal(index));
         }
+        let old_board = game_with_data.game.board.clone();
+
+        let mut moves = self.moves.get(&index).ok_or(HexError::NoMoveToTakeBack)?;
+        let record = moves.pop().ok_or(HexError::NoMoveToTakeBack)?;
+        game_with_data.undo_move(&record)?;
+        self.moves.insert(&index, &moves);
+
+        let player = game_with_data.game.player_to_move().clone();
+        env::log_str(&format!("{} took back the move {}.", player, record.cell));
+        HexEvent::TakebackAccepted {
+            game_id: index,
+            player,
+            turn: game_with_data.game.turn,
+        }
+        .emit();
+
+        game_with_data.game.board.log_changes(&old_board);
+
+        self.games.insert(&index, &game_with_data);
+        Ok(self.start_player_stream(index, &game_with_data.game))
     }
 
     #[private]
+    #[handle_result]
     pub fn resolve_streams(
         &mut self,
         game_id: GameIndex,
-        move_type: MoveType,
-        cell: Option<Cell>,
-    ) -> Promise {
-        require!(env::predecessor_account_id() == env::current_account_id());
-        require!(env::promise_results_count() == 1, "ERR_WRONG_RESULTS_COUNT");
-        let (res, stream1, stream2) = match env::promise_result(0) {
-            PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Successful(val) => {
-                if let Ok(res) =
-                    near_sdk::serde_json::from_slice::<(FinishedStreams, Stream, Stream)>(&val)
-                {
-                    res
-                } else {
-                    env::panic_str("ERR_WRONG_VAL_RECEIVED")
-                }
-            }
-            PromiseResult::Failed => env::panic_str("ERR_CALL_FAILED"),
-        };
+        action: GameAction,
+    ) -> Result<Promise, HexError> {
+        ensure(
+            env::promise_results_count() == 1,
+            HexError::WrongResultsCount,
+        )?;
+        let (res, stream1, stream2) = promise_result_json::<(FinishedStreams, Stream, Stream)>(0)?;
 
-        let bid = self.bids.get(&game_id).unwrap();
-        let mut game_with_data = self.games.get(&game_id).unwrap();
+        let bid = self.bids.get(&game_id).ok_or(HexError::NotBettingGame)?;
+        let mut game_with_data = self.games.get(&game_id).ok_or(HexError::GameNotFound)?;
 
-        match res {
-            FinishedStreams::None => {
-                Self::ext(env::current_account_id()).make_move_internal(game_id, move_type, cell)
-            }
+        Ok(match res {
+            FinishedStreams::None => match action {
+                GameAction::Move {
+                    account_id,
+                    move_type,
+                    cell,
+                } => Self::ext(env::current_account_id())
+                    .make_move_internal(game_id, account_id, move_type, cell),
+                GameAction::Takeback => {
+                    Self::ext(env::current_account_id()).takeback_internal(game_id)
+                }
+            },
             FinishedStreams::First => {
                 let bal = stream2.balance;
-                game_with_data.game.is_finished = true;
-                game_with_data.game.winner = Some(Player::First);
+                game_with_data
+                    .game
+                    .finish(Some(Player::First), FinishReason::OutOfTime);
                 self.games.insert(&game_id, &game_with_data);
                 let game = game_with_data.game;
+                HexEvent::game_finished(game_id, &game, FinishReason::OutOfTime).emit();
+                self.release_history_storage(game_id);
+                HexEvent::PayoutSent {
+                    game_id,
+                    receiver: game.first_player.clone(),
+                    amount: U128(bal + bid.bid),
+                }
+                .emit();
                 if stream2.status == StreamStatus::Paused {
                     stop_stream(stream2.id.into())
                         .then(Promise::new(game.first_player.clone()).transfer(bal + bid.bid))
@@ -151,10 +256,19 @@ impl Contract {
             }
             FinishedStreams::Second => {
                 let bal = stream1.balance;
-                game_with_data.game.is_finished = true;
-                game_with_data.game.winner = Some(Player::First);
+                game_with_data
+                    .game
+                    .finish(Some(Player::Second), FinishReason::OutOfTime);
                 self.games.insert(&game_id, &game_with_data);
                 let game = game_with_data.game;
+                HexEvent::game_finished(game_id, &game, FinishReason::OutOfTime).emit();
+                self.release_history_storage(game_id);
+                HexEvent::PayoutSent {
+                    game_id,
+                    receiver: game.second_player.clone(),
+                    amount: U128(bal + bid.bid),
+                }
+                .emit();
                 if stream1.status == StreamStatus::Paused {
                     stop_stream(stream1.id.into())
                         .then(Promise::new(game.second_player.clone()).transfer(bal + bid.bid))
@@ -163,113 +277,200 @@ impl Contract {
                 }
             }
             FinishedStreams::Both => {
-                game_with_data.game.is_finished = true;
+                game_with_data.game.finish(None, FinishReason::OutOfTime);
                 self.games.insert(&game_id, &game_with_data);
                 let game = game_with_data.game;
+                HexEvent::game_finished(game_id, &game, FinishReason::OutOfTime).emit();
+                self.release_history_storage(game_id);
+                for receiver in [&game.first_player, &game.second_player] {
+                    HexEvent::PayoutSent {
+                        game_id,
+                        receiver: receiver.clone(),
+                        amount: U128(bid.bid),
+                    }
+                    .emit();
+                }
                 Promise::new(game.first_player.clone())
                     .transfer(bid.bid)
                     .then(Promise::new(game.second_player.clone()).transfer(bid.bid))
             }
+        })
+    }
+
+    /// Starts the stream of the player whose turn it is in a wagered game.
+    fn start_player_stream(&self, index: GameIndex, game: &Game) -> Promise {
+        if let Some(bid) = self.bids.get(&index) {
+            let stream = if game.turn % 2 == 1 {
+                bid.stream_to_second_player
+            } else {
+                bid.stream_to_first_player
+            };
+            start_stream(stream).then(Self::ext(env::current_account_id()).get_game_internal(index))
+        } else {
+            Self::ext(env::current_account_id()).get_game_internal(index)
         }
     }
 
     #[private]
-    pub fn parse_two_promise_streams(&mut self) -> Promise {
-        require!(env::predecessor_account_id() == env::current_account_id());
-        require!(env::promise_results_count() == 2, "ERR_WRONG_RESULTS_COUNT");
-        let mut stream1 = match env::promise_result(0) {
-            PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Successful(val) => {
-                if let Ok(stream) = near_sdk::serde_json::from_slice::<Stream>(&val) {
-                    stream
-                } else {
-                    env::panic_str("ERR_WRONG_VAL_RECEIVED")
-                }
-            }
-            PromiseResult::Failed => env::panic_str("ERR_CALL_FAILED"),
-        };
-        let mut stream2 = match env::promise_result(1) {
-            PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Successful(val) => {
-                if let Ok(stream) = near_sdk::serde_json::from_slice::<Stream>(&val) {
-                    stream
+    #[handle_result]
+    pub fn parse_two_promise_streams(&mut self) -> Result<Promise, HexError> {
+        ensure(
+            env::promise_results_count() == 2,
+            HexError::WrongResultsCount,
+        )?;
+        let mut stream1 = promise_result_json::<Stream>(0)?;
+        let mut stream2 = promise_result_json::<Stream>(1)?;
+        Ok(
+            if stream1.status == StreamStatus::Active && stream2.status == StreamStatus::Active {
+                let promise1 = if stream1.balance == stream1.available_to_withdraw_by_formula {
+                    stream1.status = StreamStatus::Finished {
+                        reason: StreamFinishReason::StoppedByOwner,
+                    };
+                    stop_stream(stream1.id.into())
                 } else {
-                    env::panic_str("ERR_WRONG_VAL_RECEIVED")
-                }
-            }
-            PromiseResult::Failed => env::panic_str("ERR_CALL_FAILED"),
-        };
-        if stream1.status == StreamStatus::Active && stream2.status == StreamStatus::Active {
-            let promise1 = if stream1.balance == stream1.available_to_withdraw_by_formula {
-                stream1.status = StreamStatus::Finished {
-                    reason: StreamFinishReason::StoppedByOwner,
+                    stream1.status = StreamStatus::Paused;
+                    pause_stream(stream1.id.into())
                 };
-                stop_stream(stream1.id.into())
-            } else {
-                stream1.status = StreamStatus::Paused;
-                pause_stream(stream1.id.into())
-            };
-            let promise2 = if stream2.balance == stream2.available_to_withdraw_by_formula {
-                stream2.status = StreamStatus::Finished {
-                    reason: StreamFinishReason::StoppedByOwner,
+                let promise2 = if stream2.balance == stream2.available_to_withdraw_by_formula {
+                    stream2.status = StreamStatus::Finished {
+                        reason: StreamFinishReason::StoppedByOwner,
+                    };
+                    stop_stream(stream2.id.into())
+                } else {
+                    stream2.status = StreamStatus::Paused;
+                    pause_stream(stream2.id.into())
                 };
-                stop_stream(stream2.id.into())
-            } else {
-                stream2.status = StreamStatus::Paused;
-                pause_stream(stream2.id.into())
-            };
-            (promise1.and(promise2))
-                .then(Self::ext(env::current_account_id()).parse_two_streams(stream1, stream2))
-        } else if stream1.status == StreamStatus::Active {
-            let promise1 = if stream1.balance == stream1.available_to_withdraw_by_formula {
-                stream1.status = StreamStatus::Finished {
-                    reason: StreamFinishReason::StoppedByOwner,
+                (promise1.and(promise2))
+                    .then(Self::ext(env::current_account_id()).parse_two_streams(stream1, stream2))
+            } else if stream1.status == StreamStatus::Active {
+                let promise1 = if stream1.balance == stream1.available_to_withdraw_by_formula {
+                    stream1.status = StreamStatus::Finished {
+                        reason: StreamFinishReason::StoppedByOwner,
+                    };
+                    stop_stream(stream1.id.into())
+                } else {
+                    stream1.status = StreamStatus::Paused;
+                    pause_stream(stream1.id.into())
                 };
-                stop_stream(stream1.id.into())
-            } else {
-                stream1.status = StreamStatus::Paused;
-                pause_stream(stream1.id.into())
-            };
-            promise1.then(Self::ext(env::current_account_id()).parse_two_streams(stream1, stream2))
-        } else if stream2.status == StreamStatus::Active {
-            let promise2 = if stream2.balance == stream2.available_to_withdraw_by_formula {
-                stream2.status = StreamStatus::Finished {
-                    reason: StreamFinishReason::StoppedByOwner,
+                promise1
+                    .then(Self::ext(env::current_account_id()).parse_two_streams(stream1, stream2))
+            } else if stream2.status == StreamStatus::Active {
+                let promise2 = if stream2.balance == stream2.available_to_withdraw_by_formula {
+                    stream2.status = StreamStatus::Finished {
+                        reason: StreamFinishReason::StoppedByOwner,
+                    };
+                    stop_stream(stream2.id.into())
+                } else {
+                    stream2.status = StreamStatus::Paused;
+                    pause_stream(stream2.id.into())
                 };
-                stop_stream(stream2.id.into())
+                promise2
+                    .then(Self::ext(env::current_account_id()).parse_two_streams(stream1, stream2))
             } else {
-                stream2.status = StreamStatus::Paused;
-                pause_stream(stream2.id.into())
-            };
-            promise2.then(Self::ext(env::current_account_id()).parse_two_streams(stream1, stream2))
-        } else {
-            Self::ext(env::current_account_id()).parse_two_streams(stream1, stream2)
-        }
+                Self::ext(env::current_account_id()).parse_two_streams(stream1, stream2)
+            },
+        )
     }
 
     #[private]
+    #[handle_result]
     pub fn parse_two_streams(
         &mut self,
         stream1: Stream,
         stream2: Stream,
-    ) -> (FinishedStreams, Stream, Stream) {
-        require!(env::predecessor_account_id() == env::current_account_id());
-        match (
-            StatusType::new(stream1.status.clone()),
-            StatusType::new(stream2.status.clone()),
-        ) {
-            (StatusType::Active, _) => unreachable!(),
-            (_, StatusType::Active) => unreachable!(),
-            (StatusType::Paused, StatusType::Paused) => (FinishedStreams::None, stream1, stream2),
-            (StatusType::Finished, StatusType::Paused) => {
-                (FinishedStreams::First, stream1, stream2)
-            }
-            (StatusType::Paused, StatusType::Finished) => {
-                (FinishedStreams::Second, stream1, stream2)
-            }
-            (StatusType::Finished, StatusType::Finished) => {
-                (FinishedStreams::Both, stream1, stream2)
-            }
+    ) -> Result<(FinishedStreams, Stream, Stream), HexError> {
+        Ok(
+            match (
+                StatusType::new(stream1.status.clone()),
+                StatusType::new(stream2.status.clone()),
+            ) {
+                (StatusType::Active, _) => unreachable!(),
+                (_, StatusType::Active) => unreachable!(),
+                (StatusType::Paused, StatusType::Paused) => {
+                    (FinishedStreams::None, stream1, stream2)
+                }
+                (StatusType::Finished, StatusType::Paused) => {
+                    (FinishedStreams::First, stream1, stream2)
+                }
+                (StatusType::Paused, StatusType::Finished) => {
+                    (FinishedStreams::Second, stream1, stream2)
+                }
+                (StatusType::Finished, StatusType::Finished) => {
+                    (FinishedStreams::Both, stream1, stream2)
+                }
+            },
+        )
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod internal_tests {
+    use near_sdk::{
+        serde_json,
+        test_utils::{accounts, VMContextBuilder},
+        testing_env, Balance, PromiseResult, RuntimeFeesConfig, VMConfig, ONE_NEAR,
+    };
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn stream(receiver_id: AccountId, balance: Balance) -> Stream {
+        Stream {
+            id: [0; 32],
+            description: None,
+            creator_id: env::current_account_id(),
+            owner_id: env::current_account_id(),
+            receiver_id,
+            token_account_id: "wrap.testnet".parse().unwrap(),
+            timestamp_created: 0,
+            last_action: 0,
+            balance,
+            tokens_per_sec: 1,
+            status: StreamStatus::Active,
+            tokens_total_withdrawn: 0,
+            cliff: None,
+            is_expirable: false,
+            is_locked: false,
+            available_to_withdraw_by_formula: 0,
         }
     }
+
+    #[test]
+    fn test_resolve_streams_out_of_time() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = Contract::new();
+        let index = create_pending_game(
+            &mut contract,
+            GameOptions {
+                bid: Some(U128(2 * ONE_NEAR)),
+                ..Default::default()
+            },
+        );
+
+        let result = (
+            FinishedStreams::Second,
+            stream(accounts(0), 0),
+            stream(accounts(1), ONE_NEAR),
+        );
+        testing_env!(
+            VMContextBuilder::new()
+                .predecessor_account_id(env::current_account_id())
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(
+                serde_json::to_vec(&result).unwrap()
+            )],
+        );
+        let action = GameAction::Move {
+            account_id: accounts(0),
+            move_type: MoveType::PLACE,
+            cell: Some(Cell::new(0, 0)),
+        };
+        contract.resolve_streams(index, action).unwrap();
+        let game = contract.get_game(index).unwrap();
+        assert!(game.is_finished);
+        assert!(matches!(game.winner, Some(Player::Second)));
+    }
 }
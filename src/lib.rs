@@ -1,27 +1,44 @@
 use bid::Bid;
-use cell::Cell;
-use game::{Game, GameIndex};
+use game::{Game, GameIndex, GameStatus, GameSummary, Player};
+use game_settings::{GameOptions, GameSettings};
+use game_view::GameView;
 use game_with_data::GameWithData;
+use hex_rules::{Cell, SwapRule, Topology};
+use holes::Holes;
+use initial_position::InitialPosition;
+use move_record::{HistoryDeposit, MoveRecord};
+use move_verdict::MoveVerdict;
 use near_contract_standards::non_fungible_token::refund_deposit;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LookupMap;
+use near_sdk::collections::{LookupMap, TreeMap, UnorderedSet, Vector};
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
-    env, near_bindgen, require, AccountId, BorshStorageKey, PanicOnDefault, Promise, PromiseResult,
+    env, near_bindgen, AccountId, BlockHeight, BorshStorageKey, CryptoHash, PanicOnDefault, Promise,
 };
 use roketo::start_stream;
-use utils::{DEFAULT_PLAYTIME, MIN_BID};
 
+use crate::board::BoardLogs;
+use crate::challenge::{Challenge, ChallengeIndex};
+use crate::errors::{ensure, HexError};
+use crate::events::{FinishReason, HexEvent};
 use crate::external::{Stream, StreamFinishReason, StreamStatus};
+use crate::internal::GameAction;
+use crate::move_record::{history_storage, max_history_storage};
 use crate::roketo::{pause_stream, stop_stream};
-use crate::utils::{MAX_BID, MAX_PLAYTIME, MIN_PLAYTIME};
+use crate::utils::{refund_storage, DEFAULT_PAGE_SIZE};
 
 #[derive(BorshSerialize, BorshStorageKey)]
 pub enum StorageKey {
     Games,
     Field { game_id: GameIndex },
     Bid,
+    Challenges,
+    GamesByAccount,
+    AccountGames { account_hash: CryptoHash },
+    Moves,
+    GameMoves { game_id: GameIndex },
+    HistoryDeposits,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -37,6 +54,11 @@ pub struct Contract {
     pub games: LookupMap<GameIndex, GameWithData>,
     pub bids: LookupMap<GameIndex, Bid>,
     pub next_game_id: u64,
+    pub challenges: TreeMap<ChallengeIndex, Challenge>,
+    pub next_challenge_id: u64,
+    pub games_by_account: LookupMap<AccountId, UnorderedSet<GameIndex>>,
+    pub moves: LookupMap<GameIndex, Vector<MoveRecord>>,
+    pub history_deposits: LookupMap<GameIndex, HistoryDeposit>,
 }
 
 #[near_bindgen]
@@ -47,165 +69,513 @@ impl Contract {
             games: LookupMap::new(StorageKey::Games),
             bids: LookupMap::new(StorageKey::Bid),
             next_game_id: 0,
+            challenges: TreeMap::new(StorageKey::Challenges),
+            next_challenge_id: 0,
+            games_by_account: LookupMap::new(StorageKey::GamesByAccount),
+            moves: LookupMap::new(StorageKey::Moves),
+            history_deposits: LookupMap::new(StorageKey::HistoryDeposits),
         }
     }
 
     #[payable]
+    #[handle_result]
     pub fn create_game(
         &mut self,
         first_player: AccountId,
         second_player: AccountId,
-        field_size: Option<usize>,
-        bid: Option<U128>,
-        playtime: Option<u32>,
-    ) -> GameIndex {
-        if playtime.is_some() {
-            require!(
-                playtime.unwrap() >= MIN_PLAYTIME && playtime.unwrap() <= MAX_PLAYTIME,
-                "Game playtime can't be too small or too big."
-            );
-            require!(
-                bid.is_some(),
-                "You can't make game with time control without betting."
-            )
-        }
-        let game_bid = bid.map(|x| u128::from(x));
-        if game_bid.is_some() {
-            require!(
-                game_bid.unwrap() >= MIN_BID && game_bid.unwrap() <= MAX_BID,
-                "Bid can't be too small or too big."
-            );
+        options: Option<GameOptions>,
+        initial_position: Option<InitialPosition>,
+        holes: Option<Holes>,
+    ) -> Result<GameIndex, HexError> {
+        let mut options = options.unwrap_or_default();
+        if initial_position.is_some() {
+            options.swap_rule.get_or_insert(SwapRule::NoSwap);
         }
+        let settings = GameSettings::new(options)?;
+        let creator = env::predecessor_account_id();
+        ensure(
+            creator == first_player || creator == second_player,
+            HexError::NotYourGame,
+        )?;
         let initial_storage_usage = env::storage_usage();
 
-        let index = self.next_game_id;
-        let size = field_size.unwrap_or(11);
-        let game_playtime = if game_bid.is_some() {
-            if playtime.is_some() {
-                playtime
-            } else {
-                Some(DEFAULT_PLAYTIME)
-            }
-        } else {
-            None
-        };
-
-        self.games.insert(
-            &index,
-            &GameWithData::new(first_player, second_player, size, game_playtime),
+        let mut game_with_data = GameWithData::new(
+            first_player,
+            second_player,
+            settings.field_size,
+            settings.field_height,
+            settings.playtime,
+            settings.move_timeout,
         );
-
-        if game_bid.is_some() {
-            self.bids.insert(&index, &Bid::new(game_bid.unwrap()));
+        game_with_data.game.allow_takebacks = settings.allow_takebacks;
+        game_with_data.game.swap_rule = settings.swap_rule;
+        game_with_data.game.misere = settings.misere;
+        game_with_data.set_topology(settings.topology)?;
+        if let Some(holes) = holes {
+            let cells = holes.cells(&game_with_data.game.board);
+            game_with_data.add_holes(&cells)?;
+        }
+        if let Some(initial_position) = initial_position {
+            game_with_data.set_initial_position(initial_position)?;
         }
+        if creator == game_with_data.game.second_player {
+            game_with_data.game.invited_player = Player::First;
+        }
+        if game_with_data.game.first_player == game_with_data.game.second_player {
+            game_with_data.game.start();
+        }
+        let index = self.insert_game(&game_with_data, &settings);
 
-        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
+        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage
+            + max_history_storage(&game_with_data.game.board);
         refund_deposit(required_storage_in_bytes);
 
-        env::log_str("Created board:");
-        self.games.get(&index).unwrap().game.board.debug_logs();
-        self.next_game_id += 1;
-        index
+        let board = &game_with_data.game.board;
+        if board.is_logged_whole() {
+            env::log_str("Created board:");
+            board.debug_logs();
+        } else {
+            env::log_str(&format!("Created {}x{} board.", board.width, board.height));
+        }
+        if !game_with_data.game.is_started {
+            env::log_str(&format!(
+                "Waiting for {} to accept the game.",
+                game_with_data.game.invited_account()
+            ));
+        }
+        Ok(index)
+    }
+
+    #[handle_result]
+    pub fn accept_game(&mut self, index: GameIndex) -> Result<Game, HexError> {
+        let mut game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
+        ensure(!game_with_data.game.is_finished, HexError::GameFinished)?;
+        ensure(
+            !game_with_data.game.is_started,
+            HexError::GameAlreadyStarted,
+        )?;
+        ensure(
+            &env::predecessor_account_id() == game_with_data.game.invited_account(),
+            HexError::NotInvited,
+        )?;
+
+        game_with_data.game.start();
+        self.games.insert(&index, &game_with_data);
+        env::log_str("Game accepted.");
+        Ok(game_with_data.game)
+    }
+
+    #[handle_result]
+    pub fn decline_game(&mut self, index: GameIndex) -> Result<Game, HexError> {
+        let mut game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
+        ensure(!game_with_data.game.is_finished, HexError::GameFinished)?;
+        ensure(
+            !game_with_data.game.is_started,
+            HexError::GameAlreadyStarted,
+        )?;
+        let account_id = env::predecessor_account_id();
+        ensure(
+            account_id == game_with_data.game.first_player
+                || account_id == game_with_data.game.second_player,
+            HexError::NotAPlayer,
+        )?;
+
+        game_with_data.game.finish(None, FinishReason::Declined);
+        self.games.insert(&index, &game_with_data);
+        env::log_str("Game declined.");
+        HexEvent::game_finished(index, &game_with_data.game, FinishReason::Declined).emit();
+        self.release_history_storage(index);
+        Ok(game_with_data.game)
     }
 
     pub fn get_game(&self, index: GameIndex) -> Option<Game> {
         let game = self.games.get(&index).map(|x| x.game);
-        if game.is_some() {
-            env::log_str("Game board:");
-            game.clone().unwrap().board.debug_logs();
+        if let Some(board) = game.as_ref().map(|game| &game.board) {
+            if board.is_logged_whole() {
+                env::log_str("Game board:");
+                board.debug_logs();
+            } else {
+                env::log_str(&format!("Game board: {}x{}.", board.width, board.height));
+            }
         }
         game
     }
 
+    /// Returns the game with the board unpacked, the player to move, the legal
+    /// moves and the last move made.
+    pub fn get_game_view(&self, index: GameIndex) -> Option<GameView> {
+        let game = self.games.get(&index)?.game;
+        let last_move = self
+            .moves
+            .get(&index)
+            .and_then(|moves| moves.len().checked_sub(1).and_then(|last| moves.get(last)));
+        Some(GameView::new(&game, last_move))
+    }
+
+    /// Lists the games of the account with indices from `from_index` on, so
+    /// that the next page starts after the last index of the previous one.
+    pub fn get_games_for_account(
+        &self,
+        account_id: AccountId,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+        status_filter: Option<GameStatus>,
+    ) -> Vec<(GameIndex, GameSummary)> {
+        let account_games = match self.games_by_account.get(&account_id) {
+            Some(account_games) => account_games,
+            None => return Vec::new(),
+        };
+        // Games are added to the index as they are created, so the indices are
+        // sorted and the first one to list can be found by binary search.
+        let games = account_games.as_vector();
+        let from_index = from_index.unwrap_or(0);
+        let (mut start, mut end) = (0, games.len());
+        while start < end {
+            let middle = (start + end) / 2;
+            if games.get(middle).unwrap() < from_index {
+                start = middle + 1;
+            } else {
+                end = middle;
+            }
+        }
+        (start..games.len())
+            .filter_map(|position| games.get(position))
+            .map(|index| {
+                let game = self.games.get(&index).unwrap().game;
+                let bid = self.bids.get(&index).map(|bid| U128(bid.bid));
+                (index, game.summary(bid))
+            })
+            .filter(|(_, summary)| {
+                status_filter
+                    .as_ref()
+                    .is_none_or(|status| &summary.status == status)
+            })
+            .take(limit.unwrap_or(DEFAULT_PAGE_SIZE) as usize)
+            .collect()
+    }
+
+    pub fn get_move_history(
+        &self,
+        index: GameIndex,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<MoveRecord> {
+        let moves = match self.moves.get(&index) {
+            Some(moves) => moves,
+            None => return Vec::new(),
+        };
+        let from_index = from_index.unwrap_or(0);
+        let to_index = moves
+            .len()
+            .min(from_index.saturating_add(limit.unwrap_or(DEFAULT_PAGE_SIZE)));
+        (from_index..to_index)
+            .map(|i| moves.get(i).unwrap())
+            .collect()
+    }
+
+    /// Returns the chain of stones connecting the winner's borders, if the
+    /// game was finished by a connection.
+    pub fn get_winning_path(&self, index: GameIndex) -> Option<Vec<Cell>> {
+        let winning_path = self.games.get(&index)?.winning_path;
+        if winning_path.is_empty() {
+            None
+        } else {
+            Some(winning_path)
+        }
+    }
+
+    /// Runs the checks of `make_move` for the given account without making the
+    /// move, so that clients can find out whether it is legal for free.
+    pub fn validate_move(
+        &self,
+        index: GameIndex,
+        account_id: AccountId,
+        move_type: MoveType,
+        cell: Option<Cell>,
+    ) -> MoveVerdict {
+        self.check_move(index, &account_id, &move_type, &cell, false)
+            .into()
+    }
+
+    #[handle_result]
     pub fn make_move(
         &mut self,
         index: GameIndex,
         move_type: MoveType,
         cell: Option<Cell>,
+    ) -> Result<Promise, HexError> {
+        let account_id = env::predecessor_account_id();
+        self.check_move(index, &account_id, &move_type, &cell, false)?;
+        Ok(self.submit_move(index, account_id, move_type, cell))
+    }
+
+    /// Settles the clocks of a game with a bid and makes the checked move.
+    fn submit_move(
+        &mut self,
+        index: GameIndex,
+        account_id: AccountId,
+        move_type: MoveType,
+        cell: Option<Cell>,
     ) -> Promise {
-        let game_with_data = self.games.get(&index).expect("Game doesn't exist.");
-        require!(
-            !game_with_data.game.is_finished,
-            "Game is already finished!"
-        );
+        // require!(
+        //     env::prepaid_gas() >= MIN_MAKE_MOVE_GAS,
+        //     "You should attach more gas."
+        // );
+
+        if let Some(promise) = self.check_stream_bids(index) {
+            promise.then(Self::ext(env::current_account_id()).resolve_streams(
+                index,
+                GameAction::Move {
+                    account_id,
+                    move_type,
+                    cell,
+                },
+            ))
+        } else {
+            Self::ext(env::current_account_id())
+                .make_move_internal(index, account_id, move_type, cell)
+        }
+    }
+
+    /// Checks that the player can make the move. A committed move can only be
+    /// made by revealing it, which skips the check for the commitment.
+    fn check_move(
+        &self,
+        index: GameIndex,
+        account_id: &AccountId,
+        move_type: &MoveType,
+        cell: &Option<Cell>,
+        revealing: bool,
+    ) -> Result<(), HexError> {
+        let game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
+        ensure(!game_with_data.game.is_finished, HexError::GameFinished)?;
+        ensure(game_with_data.game.is_started, HexError::GameNotStarted)?;
         let bid = self.bids.get(&index);
         if let Some(bid) = bid {
-            require!(
+            ensure(
                 bid.did_first_player_bet && bid.did_second_player_bet,
-                "Players should deposit their bets before game start."
-            );
+                HexError::BetsNotPlaced,
+            )?;
         }
-        let game = game_with_data.game;
-        match (move_type.clone(), cell.clone()) {
-            (MoveType::PLACE, Some(cell)) => {
-                if game.turn % 2 == 0 {
-                    require!(
-                        env::predecessor_account_id() == game.first_player,
-                        "It's not your turn"
-                    );
-                } else {
-                    require!(
-                        env::predecessor_account_id() == game.second_player,
-                        "It's not your turn"
-                    );
-                }
-                require!(game.board.get_cell(&cell) == 0, "Cell is already filled.");
-            }
+        let game = &game_with_data.game;
+        ensure(account_id == game.player_to_move(), HexError::NotYourTurn)?;
+        ensure(
+            revealing || game.commitment.is_none(),
+            HexError::MoveAlreadyCommitted,
+        )?;
+        // Try the move on a copy of the position, so that the rules engine
+        // reports the same errors the move itself would fail with.
+        let mut position = game_with_data.position();
+        match (move_type, cell) {
+            (MoveType::PLACE, Some(cell)) => position.place(cell)?,
             (MoveType::SWAP, _) => {
-                require!(
-                    env::predecessor_account_id() == game.second_player,
-                    "Incorrect predecessor account"
-                );
-                require!(
-                    game.turn == 1,
-                    "You can apply swap rule only on the second turn"
-                );
+                position.swap(game.swap_rule)?;
             }
-            _ => env::panic_str("Incorrect move args"),
+            _ => return Err(HexError::InvalidMoveArgs),
         };
+        Ok(())
+    }
 
-        // require!(
-        //     env::prepaid_gas() >= MIN_MAKE_MOVE_GAS,
-        //     "You should attach more gas."
-        // );
+    #[handle_result]
+    pub fn resign(&mut self, index: GameIndex) -> Result<Promise, HexError> {
+        let mut game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
+        ensure(!game_with_data.game.is_finished, HexError::GameFinished)?;
+        ensure(game_with_data.game.is_started, HexError::GameNotStarted)?;
+        let bid = self.bids.get(&index);
+        if let Some(bid) = &bid {
+            ensure(
+                bid.did_first_player_bet && bid.did_second_player_bet,
+                HexError::BetsNotPlaced,
+            )?;
+        }
 
-        if let Some(promise) = self.check_stream_bids(index) {
-            promise
-                .then(Self::ext(env::current_account_id()).resolve_streams(index, move_type, cell))
+        let account_id = env::predecessor_account_id();
+        ensure(
+            account_id == game_with_data.game.first_player
+                || account_id == game_with_data.game.second_player,
+            HexError::NotAPlayer,
+        )?;
+        let winner = if account_id == game_with_data.game.first_player {
+            env::log_str("First player resigned. Second player wins!");
+            Player::Second
+        } else {
+            env::log_str("Second player resigned. First player wins!");
+            Player::First
+        };
+        game_with_data
+            .game
+            .finish(Some(winner.clone()), FinishReason::Resignation);
+        self.games.insert(&index, &game_with_data);
+        HexEvent::game_finished(index, &game_with_data.game, FinishReason::Resignation).emit();
+        self.release_history_storage(index);
+
+        Ok(if let Some(bid) = bid {
+            bid.stop_streams()
+                .then(self.player_won(index, &bid, &game_with_data.game, winner))
+                .then(Self::ext(env::current_account_id()).get_game_internal(index))
+        } else {
+            Self::ext(env::current_account_id()).get_game_internal(index)
+        })
+    }
+
+    #[handle_result]
+    pub fn claim_timeout(&mut self, index: GameIndex) -> Result<Game, HexError> {
+        let mut game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
+        ensure(!game_with_data.game.is_finished, HexError::GameFinished)?;
+        ensure(game_with_data.game.is_started, HexError::GameNotStarted)?;
+        let deadline = game_with_data
+            .game
+            .move_deadline()
+            .ok_or(HexError::NoMoveTimeout)?;
+
+        let account_id = env::predecessor_account_id();
+        let game = &game_with_data.game;
+        let winner = if game.turn.is_multiple_of(2) {
+            Player::Second
         } else {
-            Self::ext(env::current_account_id()).make_move_internal(index, move_type, cell)
+            Player::First
+        };
+        ensure(
+            &account_id == game.player_account(&winner),
+            HexError::NotWaitingPlayer,
+        )?;
+        ensure(env::block_height() > deadline, HexError::OpponentHasTime)?;
+
+        match winner {
+            Player::First => env::log_str("Second player timed out. First player wins!"),
+            Player::Second => env::log_str("First player timed out. Second player wins!"),
+        }
+        game_with_data
+            .game
+            .finish(Some(winner), FinishReason::Timeout);
+        self.games.insert(&index, &game_with_data);
+        HexEvent::game_finished(index, &game_with_data.game, FinishReason::Timeout).emit();
+        self.release_history_storage(index);
+        Ok(game_with_data.game)
+    }
+
+    pub(crate) fn insert_game(
+        &mut self,
+        game_with_data: &GameWithData,
+        settings: &GameSettings,
+    ) -> GameIndex {
+        let index = self.next_game_id;
+        self.games.insert(&index, game_with_data);
+        self.history_deposits.insert(
+            &index,
+            &HistoryDeposit {
+                payer: env::predecessor_account_id(),
+                storage: max_history_storage(&game_with_data.game.board),
+            },
+        );
+        if let Some(bid) = settings.bid {
+            self.bids.insert(&index, &Bid::new(bid.into()));
+        }
+        self.add_game_to_account(&game_with_data.game.first_player, index);
+        self.add_game_to_account(&game_with_data.game.second_player, index);
+        self.next_game_id += 1;
+
+        let game = &game_with_data.game;
+        HexEvent::GameCreated {
+            game_id: index,
+            first_player: game.first_player.clone(),
+            second_player: game.second_player.clone(),
+            field_size: game.board.width,
+            field_height: game.board.height,
+            bid: settings.bid,
+            playtime: game.playtime,
+            move_timeout: game.move_timeout,
+            swap_rule: game.swap_rule,
+            misere: game.misere,
+            topology: game.board.topology,
         }
+        .emit();
+        index
+    }
+
+    pub(crate) fn record_move(&mut self, index: GameIndex, record: MoveRecord) {
+        let mut moves = self
+            .moves
+            .get(&index)
+            .unwrap_or_else(|| Vector::new(StorageKey::GameMoves { game_id: index }));
+        moves.push(&record);
+        self.moves.insert(&index, &moves);
+    }
+
+    /// Refunds the part of the storage reserved for the move history of a
+    /// finished game that the history didn't take.
+    pub(crate) fn release_history_storage(&mut self, index: GameIndex) {
+        let storage_before_removal = env::storage_usage();
+        let deposit = match self.history_deposits.remove(&index) {
+            Some(deposit) => deposit,
+            None => return,
+        };
+        let used = history_storage(self.moves.get(&index).map_or(0, |moves| moves.len()));
+        refund_storage(
+            deposit.payer,
+            deposit.storage.saturating_sub(used) + storage_before_removal - env::storage_usage(),
+        );
+    }
+
+    fn add_game_to_account(&mut self, account_id: &AccountId, index: GameIndex) {
+        let mut account_games = self.games_by_account.get(account_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::AccountGames {
+                account_hash: env::sha256_array(account_id.as_bytes()),
+            })
+        });
+        account_games.insert(&index);
+        self.games_by_account.insert(account_id, &account_games);
     }
 }
 
 pub mod bid;
 pub mod board;
-pub mod cell;
+pub mod challenge;
+pub mod errors;
+pub mod events;
 pub mod external;
 pub mod game;
+pub mod game_settings;
+pub mod game_view;
 pub mod game_with_data;
+pub mod holes;
+pub mod initial_position;
 pub mod internal;
+pub mod move_commitment;
+pub mod move_record;
+pub mod move_verdict;
 pub mod roketo;
+pub mod sgf;
+pub mod takeback;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test_utils;
 pub mod utils;
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod contract_tests {
     use core::fmt::Debug;
     use near_sdk::{
-        test_utils::{accounts, VMContextBuilder},
-        testing_env, AccountId,
+        env,
+        mock::VmAction,
+        test_utils::{accounts, get_created_receipts, get_logs},
+        testing_env, AccountId, Balance,
     };
 
+    use hex_rules::{Board, Cell, RulesError, SwapRule, Topology};
+
     use crate::{
-        board::Board, cell::Cell, game::Game, game_with_data::GameWithData, Contract, MoveType,
+        errors::HexError,
+        game::{Game, GameIndex, GameStatus, GameSummary, Player},
+        game_view::CellState,
+        game_with_data::GameWithData,
+        holes::Holes,
+        initial_position::InitialPosition,
+        move_record::{history_storage, max_history_storage, MoveRecord},
+        test_utils::*,
+        utils::DEFAULT_MOVE_TIMEOUT,
+        Contract, GameOptions, MoveType,
     };
 
-    fn get_context(account: AccountId) -> near_sdk::VMContext {
-        VMContextBuilder::new()
-            .predecessor_account_id(account)
-            .build()
-    }
-
     impl PartialEq for Game {
         fn eq(&self, other: &Self) -> bool {
             self.first_player == other.first_player
@@ -215,12 +585,13 @@ mod contract_tests {
                 && self.current_block_height == other.current_block_height
                 && self.prev_block_height == other.prev_block_height
                 && self.is_finished == other.is_finished
+                && self.move_timeout == other.move_timeout
         }
     }
 
     impl PartialEq for GameWithData {
         fn eq(&self, other: &Self) -> bool {
-            self.game == other.game && self.data == other.data
+            self.game == other.game && self.connections == other.connections
         }
     }
 
@@ -234,6 +605,7 @@ mod contract_tests {
                 .field("current_block_height", &self.current_block_height)
                 .field("prev_block_height", &self.prev_block_height)
                 .field("is_finished", &self.is_finished)
+                .field("move_timeout", &self.move_timeout)
                 .finish()
         }
     }
@@ -242,45 +614,736 @@ mod contract_tests {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             f.debug_struct("GameWithData")
                 .field("game", &self.game)
-                .field("data", &self.data)
+                .field("connections", &self.connections)
                 .finish()
         }
     }
 
     #[test]
     fn test_create_get() {
+        testing_env!(get_context(accounts(1)));
         let mut contract = Contract::new();
-        contract.create_game(accounts(1), accounts(2), Some(3), None, None);
-        contract.create_game(accounts(4), accounts(3), Some(4), None, None);
-        let id = contract.create_game(accounts(0), accounts(1), None, None, None);
+        contract
+            .create_game(accounts(1), accounts(2), Some(with_size(3)), None, None)
+            .unwrap();
+        testing_env!(get_context(accounts(4)));
+        contract
+            .create_game(
+                accounts(4),
+                accounts(3),
+                Some(GameOptions {
+                    field_size: Some(4),
+                    field_height: Some(6),
+                    ..Default::default()
+                }),
+                None,
+                None,
+            )
+            .unwrap();
+        let id = create_pending_game(&mut contract, GameOptions::default());
         assert_eq!(id, 2);
+        let board = contract.get_game(1).unwrap().board;
+        assert_eq!((board.width, board.height), (4, 6));
         let game = contract.get_game(id);
 
         assert!(contract.get_game(id + 1).is_none());
         assert!(game.is_some());
         assert_eq!(game.clone().unwrap().first_player, accounts(0));
         assert_eq!(game.clone().unwrap().second_player, accounts(1));
-        assert_eq!(game.unwrap().board, Board::new(11));
+        assert_eq!(game.unwrap().board, Board::new(11, 11).unwrap());
     }
 
     #[test]
     fn test_make_move() {
         let mut contract = Contract::new();
-        let id = contract.create_game(accounts(0), accounts(1), Some(5), None, None);
+        let id = create_default_game(&mut contract, with_size(5));
 
         testing_env!(get_context(accounts(0)));
-        let mut test_game = GameWithData::new(accounts(0), accounts(1), 5, None);
+        let mut test_game = GameWithData::new(
+            accounts(0),
+            accounts(1),
+            5,
+            5,
+            None,
+            Some(DEFAULT_MOVE_TIMEOUT),
+        );
         assert_eq!(test_game, contract.games.get(&id).unwrap());
 
-        // let game = contract.make_move(id, MoveType::PLACE, Some(Cell::new(4, 0)));
-        test_game.make_move(MoveType::PLACE, Some(Cell::new(4, 0)));
-        // assert_eq!(test_game.game, game);
+        place_stone(&mut contract, id, accounts(0), Cell::new(4, 0));
+        test_game
+            .make_move(&accounts(0), MoveType::PLACE, Some(Cell::new(4, 0)))
+            .unwrap();
         assert_eq!(test_game, contract.games.get(&id).unwrap());
 
-        testing_env!(get_context(accounts(1)));
-        // let game = contract.make_move(id, MoveType::SWAP, Some(Cell::new(4, 0)));
-        test_game.make_move(MoveType::SWAP, Some(Cell::new(4, 0)));
-        // assert_eq!(test_game.game, game);
+        apply_move(&mut contract, id, accounts(1), MoveType::SWAP, None);
+        test_game
+            .make_move(&accounts(1), MoveType::SWAP, None)
+            .unwrap();
         assert_eq!(test_game, contract.games.get(&id).unwrap());
     }
+
+    #[test]
+    fn test_validate_move() {
+        let mut contract = Contract::new();
+        let id = create_pending_game(&mut contract, with_size(5));
+        let place = |x, y| (MoveType::PLACE, Some(Cell::new(x, y)));
+        let error = |contract: &Contract, account: AccountId, (move_type, cell)| {
+            contract.validate_move(id, account, move_type, cell).error
+        };
+        assert_eq!(
+            error(&contract, accounts(0), place(2, 2)),
+            Some("ERR_GAME_NOT_STARTED".to_string())
+        );
+
+        accept(&mut contract, id, accounts(1));
+        assert!(
+            contract
+                .validate_move(id, accounts(0), MoveType::PLACE, Some(Cell::new(2, 2)))
+                .is_legal
+        );
+        assert_eq!(
+            error(&contract, accounts(1), place(2, 2)),
+            Some("ERR_NOT_YOUR_TURN".to_string())
+        );
+        assert_eq!(
+            error(&contract, accounts(0), place(5, 0)),
+            Some("ERR_CELL_OUT_OF_BOUNDS".to_string())
+        );
+        assert_eq!(
+            error(&contract, accounts(0), (MoveType::PLACE, None)),
+            Some("ERR_INVALID_MOVE_ARGS".to_string())
+        );
+
+        place_stone(&mut contract, id, accounts(0), Cell::new(2, 2));
+        assert_eq!(
+            error(&contract, accounts(1), place(2, 2)),
+            Some("ERR_CELL_FILLED".to_string())
+        );
+        assert!(
+            contract
+                .validate_move(id, accounts(1), MoveType::SWAP, None)
+                .is_legal
+        );
+
+        place_stone(&mut contract, id, accounts(1), Cell::new(1, 1));
+        let verdict = contract.validate_move(id, accounts(0), MoveType::SWAP, None);
+        assert!(!verdict.is_legal);
+        assert_eq!(verdict.error, Some("ERR_SWAP_NOT_ALLOWED".to_string()));
+        assert_eq!(
+            verdict.message,
+            Some("You can apply swap rule only on the second turn".to_string())
+        );
+    }
+
+    #[test]
+    fn test_get_game_view() {
+        let mut contract = Contract::new();
+        let id = create_pending_game(&mut contract, with_size(3));
+        assert!(contract.get_game_view(id + 1).is_none());
+        let view = contract.get_game_view(id).unwrap();
+        assert!(view.last_move.is_none());
+        assert!(view.player_to_move.is_none());
+
+        accept(&mut contract, id, accounts(1));
+        place_stone(&mut contract, id, accounts(0), Cell::new(2, 0));
+        apply_move(&mut contract, id, accounts(1), MoveType::SWAP, None);
+        let view = contract.get_game_view(id).unwrap();
+        assert_eq!(view.board[2][0], CellState::Blue);
+        assert_eq!(view.player_to_move, Some(accounts(0)));
+        assert_eq!(view.color_to_move, Some(CellState::Red));
+        assert!(!view.is_swap_legal);
+        assert_eq!(view.empty_cells.len(), 8);
+        let last_move = view.last_move.unwrap();
+        assert!(matches!(last_move.move_type, MoveType::SWAP));
+        assert_eq!(last_move.cell, Cell::new(0, 2));
+    }
+
+    #[test]
+    fn test_create_game_with_holes() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = Contract::new();
+        let holes = Holes::Cells(vec![Cell::new(1, 0), Cell::new(0, 1)]);
+        let create_game = |contract: &mut Contract, field_size, holes| {
+            contract.create_game(
+                accounts(0),
+                accounts(1),
+                Some(with_size(field_size)),
+                None,
+                Some(holes),
+            )
+        };
+        assert_eq!(
+            create_game(&mut contract, 2, Holes::Random(3)),
+            Err(HexError::InvalidHoles)
+        );
+        let id = create_game(&mut contract, 2, holes).unwrap();
+        accept(&mut contract, id, accounts(1));
+        let view = contract.get_game_view(id).unwrap();
+        assert_eq!(view.board[0][1], CellState::Blocked);
+        assert_eq!(view.empty_cells, vec![Cell::new(0, 0), Cell::new(1, 1)]);
+        assert_eq!(
+            contract
+                .validate_move(id, accounts(0), MoveType::PLACE, Some(Cell::new(0, 1)))
+                .error,
+            Some("ERR_CELL_BLOCKED".to_string())
+        );
+
+        place_stone(&mut contract, id, accounts(0), Cell::new(0, 0));
+        place_stone(&mut contract, id, accounts(1), Cell::new(1, 1));
+        let game = contract.get_game(id).unwrap();
+        assert!(game.is_finished);
+        assert!(game.winner.is_none());
+    }
+
+    #[test]
+    fn test_misere_game() {
+        let mut contract = Contract::new();
+        let id = create_pending_game(
+            &mut contract,
+            GameOptions {
+                field_size: Some(2),
+                swap_rule: Some(SwapRule::NoSwap),
+                misere: Some(true),
+                ..Default::default()
+            },
+        );
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""swap_rule":"NoSwap","misere":true"#)));
+        accept(&mut contract, id, accounts(1));
+        place_stone(&mut contract, id, accounts(0), Cell::new(0, 0));
+        place_stone(&mut contract, id, accounts(1), Cell::new(1, 0));
+        place_stone(&mut contract, id, accounts(0), Cell::new(0, 1));
+        assert!(get_logs().contains(&"Second player wins!".to_string()));
+        assert!(get_logs().contains(&"Losing path: a1 a2.".to_string()));
+        let game = contract.get_game(id).unwrap();
+        assert!(game.misere);
+        assert!(matches!(game.winner, Some(Player::Second)));
+    }
+
+    #[test]
+    fn test_game_of_y() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = Contract::new();
+        let create_game = |contract: &mut Contract, field_height| {
+            contract.create_game(
+                accounts(0),
+                accounts(1),
+                Some(GameOptions {
+                    field_size: Some(3),
+                    field_height,
+                    swap_rule: Some(SwapRule::NoSwap),
+                    topology: Some(Topology::Y),
+                    ..Default::default()
+                }),
+                None,
+                None,
+            )
+        };
+        assert_eq!(
+            create_game(&mut contract, Some(4)),
+            Err(HexError::Rules(RulesError::InvalidSize))
+        );
+        let id = create_game(&mut contract, None).unwrap();
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains(r#""misere":false,"topology":"Y""#)));
+        accept(&mut contract, id, accounts(1));
+        let view = contract.get_game_view(id).unwrap();
+        assert_eq!(
+            view.board.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![3, 2, 1]
+        );
+        assert_eq!(
+            contract
+                .validate_move(id, accounts(0), MoveType::PLACE, Some(Cell::new(1, 2)))
+                .error,
+            Some("ERR_CELL_OUT_OF_BOUNDS".to_string())
+        );
+
+        for (account, x, y) in [(0, 0, 0), (1, 2, 0), (0, 0, 1), (1, 1, 1), (0, 0, 2)] {
+            apply_move(
+                &mut contract,
+                id,
+                accounts(account),
+                MoveType::PLACE,
+                Some(Cell::new(x, y)),
+            );
+        }
+        let game = contract.get_game(id).unwrap();
+        assert!(matches!(game.winner, Some(Player::First)));
+        assert_eq!(
+            contract.get_winning_path(id).unwrap(),
+            vec![Cell::new(0, 0), Cell::new(0, 1), Cell::new(0, 2)]
+        );
+        assert!(contract.get_game_sgf(id).is_none());
+    }
+
+    #[test]
+    fn test_create_game_with_initial_position() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = Contract::new();
+        let initial_position = InitialPosition {
+            red: vec![],
+            blue: vec![Cell::new(1, 1), Cell::new(2, 2)],
+            blue_moves_first: true,
+        };
+        let id = contract
+            .create_game(
+                accounts(0),
+                accounts(1),
+                Some(with_size(5)),
+                Some(initial_position.clone()),
+                None,
+            )
+            .unwrap();
+        accept(&mut contract, id, accounts(1));
+        let game = contract.get_game(id).unwrap();
+        assert_eq!(game.initial_position, Some(initial_position.clone()));
+        assert_eq!(game.board.get_cell(&Cell::new(2, 2)), 2);
+        assert_eq!(game.player_to_move(), &accounts(1));
+        assert_eq!(game.swap_rule, SwapRule::NoSwap);
+
+        let verdict = contract.validate_move(id, accounts(1), MoveType::SWAP, None);
+        assert_eq!(verdict.error, Some("ERR_SWAP_DISABLED".to_string()));
+        place_stone(&mut contract, id, accounts(1), Cell::new(0, 0));
+        assert_eq!(contract.get_game(id).unwrap().turn, 2);
+        assert_eq!(
+            contract.get_game_sgf(id).unwrap(),
+            "(;FF[4]GM[11]AP[hex-game]SZ[5]PB[alice]PW[bob]AW[b2][c3]PL[W];W[a1])"
+        );
+
+        testing_env!(get_context(accounts(0)));
+        let won = InitialPosition {
+            red: (0..5).map(|y| Cell::new(0, y)).collect(),
+            ..Default::default()
+        };
+        assert_eq!(
+            contract.create_game(
+                accounts(0),
+                accounts(1),
+                Some(with_size(5)),
+                Some(won),
+                None
+            ),
+            Err(HexError::InvalidInitialPosition)
+        );
+        let options = GameOptions {
+            swap_rule: Some(SwapRule::SwapSides),
+            ..with_size(5)
+        };
+        assert_eq!(
+            contract.create_game(
+                accounts(0),
+                accounts(1),
+                Some(options),
+                Some(initial_position),
+                None
+            ),
+            Err(HexError::SwapWithInitialPosition)
+        );
+    }
+
+    #[test]
+    fn test_swap_rules() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = Contract::new();
+        let mut create_game = |field_height, swap_rule| {
+            contract.create_game(
+                accounts(0),
+                accounts(1),
+                Some(GameOptions {
+                    field_size: Some(5),
+                    field_height: Some(field_height),
+                    swap_rule,
+                    ..Default::default()
+                }),
+                None,
+                None,
+            )
+        };
+        assert_eq!(
+            create_game(4, Some(SwapRule::SwapPieces)),
+            Err(HexError::InvalidSwapRule)
+        );
+        let no_swap = create_game(5, Some(SwapRule::NoSwap)).unwrap();
+        let swap_sides = create_game(5, Some(SwapRule::SwapSides)).unwrap();
+        let default = create_game(4, None).unwrap();
+        assert_eq!(
+            contract.get_game(default).unwrap().swap_rule,
+            SwapRule::SwapSides
+        );
+
+        for id in [no_swap, swap_sides] {
+            accept(&mut contract, id, accounts(1));
+            place_stone(&mut contract, id, accounts(0), Cell::new(3, 0));
+        }
+        let verdict = contract.validate_move(no_swap, accounts(1), MoveType::SWAP, None);
+        assert_eq!(verdict.error, Some("ERR_SWAP_DISABLED".to_string()));
+
+        apply_move(&mut contract, swap_sides, accounts(1), MoveType::SWAP, None);
+        assert!(get_logs().contains(&"bob swapped sides, taking the stone on d1.".to_string()));
+        let game = contract.get_game(swap_sides).unwrap();
+        assert!(game.sides_swapped);
+        assert_eq!(game.board.get_cell(&Cell::new(3, 0)), 1);
+        let history = contract.get_move_history(swap_sides, None, None);
+        assert_eq!(history[0].swap_rule, None);
+        assert_eq!(history[1].swap_rule, Some(SwapRule::SwapSides));
+        assert_eq!(history[1].cell, Cell::new(3, 0));
+        assert!(contract
+            .get_game_sgf(swap_sides)
+            .unwrap()
+            .ends_with(";B[d1];W[swap-sides])"));
+    }
+
+    #[test]
+    fn test_get_winning_path() {
+        let mut contract = Contract::new();
+        let id = create_default_game(&mut contract, with_size(3));
+        let moves = [(1, 0), (2, 2), (1, 1), (0, 2), (1, 2)];
+        for (i, &(x, y)) in moves.iter().enumerate() {
+            assert!(contract.get_winning_path(id).is_none());
+            apply_move(
+                &mut contract,
+                id,
+                accounts(i % 2),
+                MoveType::PLACE,
+                Some(Cell::new(x, y)),
+            );
+        }
+
+        assert!(get_logs().contains(&"Winning path: b1 b2 b3.".to_string()));
+        assert_eq!(
+            contract.get_winning_path(id).unwrap(),
+            vec![Cell::new(1, 0), Cell::new(1, 1), Cell::new(1, 2)]
+        );
+    }
+
+    #[test]
+    fn test_large_board() {
+        let mut contract = Contract::new();
+        let id = create_pending_game(&mut contract, with_size(25));
+        assert_eq!(get_logs()[1], "Created 25x25 board.");
+        accept(&mut contract, id, accounts(1));
+        place_stone(&mut contract, id, accounts(0), Cell::new(12, 12));
+        assert!(get_logs().contains(&"Changed cells: m13 . -> R.".to_string()));
+        let logs = get_logs().len();
+        contract.get_game(id).unwrap();
+        assert_eq!(get_logs()[logs..], ["Game board: 25x25.".to_string()]);
+    }
+
+    #[test]
+    fn test_resign() {
+        let mut contract = Contract::new();
+        let id = create_default_game(&mut contract, with_size(5));
+        place_stone(&mut contract, id, accounts(0), Cell::new(2, 2));
+
+        testing_env!(get_context(accounts(0)));
+        contract.resign(id).unwrap();
+        let game = contract.get_game(id).unwrap();
+        assert!(game.is_finished);
+        assert!(matches!(game.winner, Some(Player::Second)));
+    }
+
+    #[test]
+    fn test_resign_not_a_player() {
+        let mut contract = Contract::new();
+        let id = create_default_game(&mut contract, with_size(5));
+
+        testing_env!(get_context(accounts(2)));
+        assert_eq!(contract.resign(id).err(), Some(HexError::NotAPlayer));
+    }
+
+    #[test]
+    fn test_resign_finished_game() {
+        let mut contract = Contract::new();
+        let id = create_default_game(&mut contract, with_size(5));
+
+        testing_env!(get_context(accounts(1)));
+        contract.resign(id).unwrap();
+        assert_eq!(contract.resign(id).err(), Some(HexError::GameFinished));
+    }
+
+    #[test]
+    fn test_move_history_storage() {
+        let mut contract = Contract::new();
+        let id = create_default_game(&mut contract, with_size(5));
+        let storage = env::storage_usage();
+        for moves in 1..=3 {
+            let mut record = MoveRecord::new(MoveType::SWAP, Cell::new(25, 25));
+            record.swap_rule = Some(SwapRule::SwapSides);
+            contract.record_move(id, record);
+            assert_eq!(env::storage_usage() - storage, history_storage(moves));
+        }
+    }
+
+    #[test]
+    fn test_history_storage_refund() {
+        let mut contract = Contract::new();
+        let id = create_default_game(&mut contract, with_size(5));
+        place_stone(&mut contract, id, accounts(0), Cell::new(2, 2));
+        place_stone(&mut contract, id, accounts(1), Cell::new(1, 2));
+
+        testing_env!(get_context(accounts(0)));
+        contract.resign(id).unwrap();
+        let board = &contract.get_game(id).unwrap().board;
+        let unused = max_history_storage(board) - history_storage(2);
+        let refunds: Vec<Balance> = get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == accounts(0))
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                VmAction::Transfer { deposit } => Some(deposit),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(refunds.len(), 1);
+        assert!(refunds[0] >= env::storage_byte_cost() * Balance::from(unused));
+        assert!(contract.history_deposits.get(&id).is_none());
+    }
+
+    #[test]
+    fn test_claim_timeout() {
+        let mut contract = Contract::new();
+        let id = create_default_game(
+            &mut contract,
+            GameOptions {
+                field_size: Some(5),
+                move_timeout: Some(300),
+                ..Default::default()
+            },
+        );
+        place_stone(&mut contract, id, accounts(0), Cell::new(2, 2));
+
+        testing_env!(get_context_at(accounts(0), 301));
+        let game = contract.claim_timeout(id).unwrap();
+        assert!(game.is_finished);
+        assert!(matches!(game.winner, Some(Player::First)));
+    }
+
+    #[test]
+    fn test_claim_timeout_too_early() {
+        let mut contract = Contract::new();
+        let id = create_default_game(
+            &mut contract,
+            GameOptions {
+                field_size: Some(5),
+                move_timeout: Some(300),
+                ..Default::default()
+            },
+        );
+
+        testing_env!(get_context_at(accounts(1), 300));
+        assert_eq!(
+            contract.claim_timeout(id).err(),
+            Some(HexError::OpponentHasTime)
+        );
+    }
+
+    #[test]
+    fn test_claim_timeout_by_player_to_move() {
+        let mut contract = Contract::new();
+        let id = create_default_game(
+            &mut contract,
+            GameOptions {
+                field_size: Some(5),
+                move_timeout: Some(300),
+                ..Default::default()
+            },
+        );
+
+        testing_env!(get_context_at(accounts(0), 1000));
+        assert_eq!(
+            contract.claim_timeout(id).err(),
+            Some(HexError::NotWaitingPlayer)
+        );
+    }
+
+    #[test]
+    fn test_create_game_for_other_accounts() {
+        testing_env!(get_context(accounts(0)));
+        let mut contract = Contract::new();
+        assert_eq!(
+            contract
+                .create_game(accounts(1), accounts(2), Some(with_size(5)), None, None)
+                .err(),
+            Some(HexError::NotYourGame)
+        );
+    }
+
+    #[test]
+    fn test_accept_game() {
+        testing_env!(get_context(accounts(1)));
+        let mut contract = Contract::new();
+        let id = contract
+            .create_game(accounts(0), accounts(1), Some(with_size(5)), None, None)
+            .unwrap();
+        let game = contract.get_game(id).unwrap();
+        assert!(!game.is_started);
+        assert!(matches!(game.invited_player, Player::First));
+
+        accept(&mut contract, id, accounts(0));
+        assert!(contract.get_game(id).unwrap().is_started);
+        place_stone(&mut contract, id, accounts(0), Cell::new(2, 2));
+        assert_eq!(contract.get_game(id).unwrap().turn, 1);
+    }
+
+    #[test]
+    fn test_accept_game_by_creator() {
+        let mut contract = Contract::new();
+        let id = create_pending_game(&mut contract, with_size(5));
+        assert_eq!(contract.accept_game(id).err(), Some(HexError::NotInvited));
+    }
+
+    #[test]
+    fn test_make_move_pending_game() {
+        let mut contract = Contract::new();
+        let id = create_pending_game(&mut contract, with_size(5));
+        assert_eq!(
+            contract
+                .make_move(id, MoveType::PLACE, Some(Cell::new(2, 2)))
+                .err(),
+            Some(HexError::GameNotStarted)
+        );
+    }
+
+    #[test]
+    fn test_decline_game() {
+        let mut contract = Contract::new();
+        let id = create_pending_game(&mut contract, with_size(5));
+
+        testing_env!(get_context(accounts(1)));
+        let game = contract.decline_game(id).unwrap();
+        assert!(game.is_finished);
+        assert!(!game.is_started);
+        assert!(game.winner.is_none());
+    }
+
+    #[test]
+    fn test_accept_declined_game() {
+        let mut contract = Contract::new();
+        let id = create_pending_game(&mut contract, with_size(5));
+
+        testing_env!(get_context(accounts(1)));
+        contract.decline_game(id).unwrap();
+        assert_eq!(contract.accept_game(id).err(), Some(HexError::GameFinished));
+    }
+
+    #[test]
+    fn test_get_games_for_account() {
+        let mut contract = Contract::new();
+        let first = create_pending_game(&mut contract, with_size(5));
+        let second = contract
+            .create_game(accounts(0), accounts(2), Some(with_size(7)), None, None)
+            .unwrap();
+        testing_env!(get_context(accounts(1)));
+        let third = contract
+            .create_game(accounts(1), accounts(2), Some(with_size(9)), None, None)
+            .unwrap();
+        accept(&mut contract, second, accounts(2));
+
+        let indices = |games: Vec<(GameIndex, GameSummary)>| {
+            games
+                .into_iter()
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            indices(contract.get_games_for_account(accounts(0), None, None, None)),
+            vec![first, second]
+        );
+        assert_eq!(
+            indices(contract.get_games_for_account(accounts(2), None, None, None)),
+            vec![second, third]
+        );
+        assert_eq!(
+            indices(contract.get_games_for_account(accounts(2), Some(second), Some(1), None)),
+            vec![second]
+        );
+        assert_eq!(
+            indices(contract.get_games_for_account(accounts(2), Some(second + 1), None, None)),
+            vec![third]
+        );
+        assert!(contract
+            .get_games_for_account(
+                accounts(2),
+                Some(second + 1),
+                None,
+                Some(GameStatus::Active)
+            )
+            .is_empty());
+        assert_eq!(
+            indices(contract.get_games_for_account(
+                accounts(2),
+                None,
+                None,
+                Some(GameStatus::Active)
+            )),
+            vec![second]
+        );
+        assert!(contract
+            .get_games_for_account(accounts(3), None, None, None)
+            .is_empty());
+
+        let (_, summary) = contract
+            .get_games_for_account(accounts(1), None, Some(1), None)
+            .pop()
+            .unwrap();
+        assert_eq!(summary.field_size, 5);
+        assert!(summary.status == GameStatus::Pending);
+    }
+
+    #[test]
+    fn test_events() {
+        let mut contract = Contract::new();
+        let id = create_pending_game(&mut contract, with_size(2));
+        assert!(
+            get_logs()
+                .iter()
+                .any(|log| log.starts_with("EVENT_JSON:")
+                    && log.contains(r#""event":"game_created""#))
+        );
+
+        accept(&mut contract, id, accounts(1));
+        place_stone(&mut contract, id, accounts(0), Cell::new(1, 0));
+        assert!(get_logs().contains(
+            &r#"EVENT_JSON:{"standard":"hex-game","version":"1.0.0","event":"move_made","data":{"game_id":0,"player":"alice","move_type":"PLACE","cell":{"x":1,"y":0},"turn":0}}"#
+                .to_string()
+        ));
+
+        apply_move(&mut contract, id, accounts(1), MoveType::SWAP, None);
+        assert!(get_logs().iter().any(|log| log.contains(
+            r#""event":"swap_applied","data":{"game_id":0,"player":"bob","from":{"x":1,"y":0},"to":{"x":0,"y":1},"rule":"SwapPieces","turn":1}"#
+        )));
+
+        place_stone(&mut contract, id, accounts(0), Cell::new(1, 0));
+        place_stone(&mut contract, id, accounts(1), Cell::new(1, 1));
+        assert!(get_logs().iter().any(|log| log.contains(
+            r#""event":"game_finished","data":{"game_id":0,"winner":"bob","reason":"connection"}"#
+        )));
+    }
+
+    #[test]
+    fn test_move_history() {
+        let mut contract = Contract::new();
+        let id = create_default_game(&mut contract, with_size(5));
+        assert!(contract.get_move_history(id, None, None).is_empty());
+
+        place_stone(&mut contract, id, accounts(0), Cell::new(3, 0));
+        apply_move(&mut contract, id, accounts(1), MoveType::SWAP, None);
+        place_stone(&mut contract, id, accounts(0), Cell::new(2, 2));
+
+        let history = contract.get_move_history(id, None, None);
+        assert_eq!(history.len(), 3);
+        assert!(matches!(history[0].move_type, MoveType::PLACE));
+        assert_eq!(history[0].cell, Cell::new(3, 0));
+        assert!(matches!(history[1].move_type, MoveType::SWAP));
+        assert_eq!(history[1].cell, Cell::new(0, 3));
+        assert_eq!(history[2].cell, Cell::new(2, 2));
+
+        let page = contract.get_move_history(id, Some(1), Some(1));
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].cell, Cell::new(0, 3));
+        assert!(contract.get_move_history(id, Some(5), None).is_empty());
+    }
 }
@@ -0,0 +1,376 @@
+use std::convert::TryInto;
+
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::{BlockHeight, CryptoHash};
+
+use crate::errors::{ensure, HexError};
+use crate::utils::REVEAL_TIMEOUT;
+use crate::*;
+
+/// Hash of the move the player to move committed to in a game with a bid, so
+/// that the move can't be seen and front-run before it is revealed.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MoveCommitment {
+    pub player: Player,
+    pub hash: Base58CryptoHash,
+    pub block_height: BlockHeight,
+}
+
+impl MoveCommitment {
+    pub fn new(player: Player, hash: Base58CryptoHash) -> Self {
+        Self {
+            player,
+            hash,
+            block_height: env::block_height(),
+        }
+    }
+
+    /// Hash to commit to: sha256 of `<move_type>:<cell>:<salt>`, e.g.
+    /// `PLACE:c3:8f2kq0`, with an empty cell for the swap (`SWAP::8f2kq0`),
+    /// even if one is passed.
+    pub fn hash_move(move_type: &MoveType, cell: &Option<Cell>, salt: &str) -> Base58CryptoHash {
+        let (move_type, cell) = match move_type {
+            MoveType::PLACE => ("PLACE", cell.as_ref()),
+            MoveType::SWAP => ("SWAP", None),
+        };
+        let cell = cell.map(ToString::to_string).unwrap_or_default();
+        let hash: CryptoHash = env::sha256(format!("{}:{}:{}", move_type, cell, salt).as_bytes())
+            .try_into()
+            .unwrap();
+        hash.into()
+    }
+
+    pub fn reveal_deadline(&self) -> BlockHeight {
+        self.block_height + REVEAL_TIMEOUT
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Commits the predecessor to their next move in a game with a bid. The
+    /// clock of the player keeps running until the move is revealed.
+    #[handle_result]
+    pub fn commit_move(
+        &mut self,
+        index: GameIndex,
+        hash: Base58CryptoHash,
+    ) -> Result<Game, HexError> {
+        let mut game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
+        let bid = self.bids.get(&index).ok_or(HexError::NotBettingGame)?;
+        let game = &mut game_with_data.game;
+        ensure(!game.is_finished, HexError::GameFinished)?;
+        ensure(game.is_started, HexError::GameNotStarted)?;
+        ensure(
+            bid.did_first_player_bet && bid.did_second_player_bet,
+            HexError::BetsNotPlaced,
+        )?;
+        let account_id = env::predecessor_account_id();
+        ensure(&account_id == game.player_to_move(), HexError::NotYourTurn)?;
+        ensure(game.commitment.is_none(), HexError::MoveAlreadyCommitted)?;
+        let player = if game.turn.is_multiple_of(2) {
+            Player::First
+        } else {
+            Player::Second
+        };
+
+        game.commitment = Some(MoveCommitment::new(player, hash));
+        self.games.insert(&index, &game_with_data);
+        let game = game_with_data.game;
+        env::log_str(&format!("{} committed a move.", account_id));
+        HexEvent::MoveCommitted {
+            game_id: index,
+            player: account_id,
+            turn: game.turn,
+        }
+        .emit();
+        Ok(game)
+    }
+
+    /// Makes the committed move, which has to match the hash, see
+    /// `MoveCommitment::hash_move`. The cell of a swap is ignored.
+    #[handle_result]
+    pub fn reveal_move(
+        &mut self,
+        index: GameIndex,
+        move_type: MoveType,
+        cell: Option<Cell>,
+        salt: String,
+    ) -> Result<Promise, HexError> {
+        let cell = match move_type {
+            MoveType::PLACE => cell,
+            MoveType::SWAP => None,
+        };
+        let game = self.games.get(&index).ok_or(HexError::GameNotFound)?.game;
+        let commitment = game.commitment.as_ref().ok_or(HexError::NoCommittedMove)?;
+        let account_id = env::predecessor_account_id();
+        ensure(
+            &account_id == game.player_account(&commitment.player),
+            HexError::NotYourTurn,
+        )?;
+        self.check_move(index, &account_id, &move_type, &cell, true)?;
+        ensure(
+            commitment.hash == MoveCommitment::hash_move(&move_type, &cell, &salt),
+            HexError::CommitmentMismatch,
+        )?;
+        Ok(self.submit_move(index, account_id, move_type, cell))
+    }
+
+    /// Ends the game in favor of the predecessor if the opponent committed a
+    /// move, but didn't reveal it in time.
+    #[handle_result]
+    pub fn claim_reveal_timeout(&mut self, index: GameIndex) -> Result<Promise, HexError> {
+        let mut game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
+        let bid = self.bids.get(&index).ok_or(HexError::NotBettingGame)?;
+        let game = &game_with_data.game;
+        ensure(!game.is_finished, HexError::GameFinished)?;
+        let commitment = game.commitment.as_ref().ok_or(HexError::NoCommittedMove)?;
+        let winner = match commitment.player {
+            Player::First => Player::Second,
+            Player::Second => Player::First,
+        };
+        ensure(
+            &env::predecessor_account_id() == game.player_account(&winner),
+            HexError::NotWaitingPlayer,
+        )?;
+        ensure(
+            env::block_height() > commitment.reveal_deadline(),
+            HexError::OpponentHasTime,
+        )?;
+
+        match winner {
+            Player::First => {
+                env::log_str("Second player didn't reveal the move. First player wins!")
+            }
+            Player::Second => {
+                env::log_str("First player didn't reveal the move. Second player wins!")
+            }
+        }
+        game_with_data
+            .game
+            .finish(Some(winner.clone()), FinishReason::Timeout);
+        self.games.insert(&index, &game_with_data);
+        HexEvent::game_finished(index, &game_with_data.game, FinishReason::Timeout).emit();
+        self.release_history_storage(index);
+        Ok(bid
+            .stop_streams()
+            .then(self.player_won(index, &bid, &game_with_data.game, winner))
+            .then(Self::ext(env::current_account_id()).get_game_internal(index)))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod move_commitment_tests {
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts};
+    use near_sdk::{testing_env, ONE_NEAR};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    /// A game on a 3x3 board with a bid, so that moves can be committed.
+    fn wagered_game(contract: &mut Contract) -> GameIndex {
+        create_default_game(
+            contract,
+            GameOptions {
+                field_size: Some(3),
+                bid: Some(U128(2 * ONE_NEAR)),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn new_game(contract: &mut Contract) -> GameIndex {
+        let index = wagered_game(contract);
+        place_bets(contract, index);
+        index
+    }
+
+    fn place(x: usize, y: usize) -> (MoveType, Option<Cell>) {
+        (MoveType::PLACE, Some(Cell::new(x, y)))
+    }
+
+    #[test]
+    fn test_hash_move() {
+        let (move_type, cell) = place(2, 0);
+        assert_eq!(
+            MoveCommitment::hash_move(&move_type, &cell, "salt"),
+            env::sha256(b"PLACE:c1:salt")
+                .try_into()
+                .map(|hash: CryptoHash| hash.into())
+                .unwrap()
+        );
+        assert_ne!(
+            MoveCommitment::hash_move(&move_type, &cell, "salt"),
+            MoveCommitment::hash_move(&MoveType::SWAP, &None, "salt")
+        );
+        assert_eq!(
+            MoveCommitment::hash_move(&MoveType::SWAP, &cell, "salt"),
+            MoveCommitment::hash_move(&MoveType::SWAP, &None, "salt")
+        );
+    }
+
+    #[test]
+    fn test_commit_and_reveal() {
+        let mut contract = Contract::new();
+        let index = wagered_game(&mut contract);
+        let (move_type, cell) = place(1, 1);
+        let hash = MoveCommitment::hash_move(&move_type, &cell, "salt");
+
+        testing_env!(get_context_at(accounts(0), 2));
+        assert_eq!(
+            contract.commit_move(index, hash).err(),
+            Some(HexError::BetsNotPlaced)
+        );
+        let index = new_game(&mut contract);
+        testing_env!(get_context_at(accounts(1), 2));
+        assert_eq!(
+            contract.commit_move(index, hash).err(),
+            Some(HexError::NotYourTurn)
+        );
+        testing_env!(get_context_at(accounts(0), 2));
+        assert_eq!(
+            contract
+                .reveal_move(index, move_type.clone(), cell.clone(), "salt".to_string())
+                .err(),
+            Some(HexError::NoCommittedMove)
+        );
+        let game = contract.commit_move(index, hash).unwrap();
+        assert_eq!(game.commitment.unwrap().block_height, 2);
+        assert_eq!(
+            contract.commit_move(index, hash).err(),
+            Some(HexError::MoveAlreadyCommitted)
+        );
+        assert_eq!(
+            contract
+                .make_move(index, move_type.clone(), cell.clone())
+                .err(),
+            Some(HexError::MoveAlreadyCommitted)
+        );
+        let verdict = contract.validate_move(index, accounts(0), move_type.clone(), cell.clone());
+        assert_eq!(
+            verdict.error.as_deref(),
+            Some(HexError::MoveAlreadyCommitted.code())
+        );
+
+        let (other_type, other_cell) = place(2, 2);
+        assert_eq!(
+            contract
+                .reveal_move(index, other_type, other_cell, "salt".to_string())
+                .err(),
+            Some(HexError::CommitmentMismatch)
+        );
+        assert!(contract
+            .reveal_move(index, move_type.clone(), cell.clone(), "salt".to_string())
+            .is_ok());
+
+        testing_env!(get_context_at(env::current_account_id(), 3));
+        contract
+            .make_move_internal(index, accounts(0), move_type, cell)
+            .unwrap();
+        let game = contract.get_game(index).unwrap();
+        assert!(game.commitment.is_none());
+        assert_eq!(game.turn, 1);
+    }
+
+    #[test]
+    fn test_reveal_swap_ignores_cell() {
+        let mut contract = Contract::new();
+        let index = new_game(&mut contract);
+        let (move_type, cell) = place(1, 1);
+        testing_env!(get_context_at(env::current_account_id(), 2));
+        contract
+            .make_move_internal(index, accounts(0), move_type, cell.clone())
+            .unwrap();
+
+        testing_env!(get_context_at(accounts(1), 3));
+        let hash = MoveCommitment::hash_move(&MoveType::SWAP, &None, "salt");
+        contract.commit_move(index, hash).unwrap();
+        assert!(contract
+            .reveal_move(index, MoveType::SWAP, cell, "salt".to_string())
+            .is_ok());
+        let swaps: Vec<String> = get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                VmAction::FunctionCall { args, .. } => String::from_utf8(args).ok(),
+                _ => None,
+            })
+            .filter(|args| args.contains(r#""move_type":"SWAP""#))
+            .collect();
+        assert!(!swaps.is_empty());
+        assert!(swaps.iter().all(|args| args.contains(r#""cell":null"#)));
+    }
+
+    #[test]
+    fn test_claim_reveal_timeout() {
+        let mut contract = Contract::new();
+        let index = new_game(&mut contract);
+        testing_env!(get_context_at(accounts(1), 2));
+        assert_eq!(
+            contract.claim_reveal_timeout(index).err(),
+            Some(HexError::NoCommittedMove)
+        );
+        testing_env!(get_context_at(accounts(0), 2));
+        contract
+            .commit_move(index, Base58CryptoHash::default())
+            .unwrap();
+        assert_eq!(
+            contract.claim_reveal_timeout(index).err(),
+            Some(HexError::NotWaitingPlayer)
+        );
+
+        testing_env!(get_context_at(accounts(1), 2 + REVEAL_TIMEOUT));
+        assert_eq!(
+            contract.claim_reveal_timeout(index).err(),
+            Some(HexError::OpponentHasTime)
+        );
+        testing_env!(get_context_at(accounts(1), 3 + REVEAL_TIMEOUT));
+        assert!(contract.claim_reveal_timeout(index).is_ok());
+        let game = contract.get_game(index).unwrap();
+        assert!(game.is_finished);
+        assert!(matches!(game.winner, Some(Player::Second)));
+    }
+
+    #[test]
+    fn test_takeback_drops_commitment() {
+        let mut contract = Contract::new();
+        let index = new_game(&mut contract);
+        let mut game_with_data = contract.games.get(&index).unwrap();
+        game_with_data.game.allow_takebacks = true;
+        contract.games.insert(&index, &game_with_data);
+
+        let (move_type, cell) = place(1, 1);
+        testing_env!(get_context_at(accounts(0), 2));
+        contract
+            .make_move(index, move_type.clone(), cell.clone())
+            .unwrap();
+        testing_env!(get_context_at(env::current_account_id(), 2));
+        contract
+            .make_move_internal(index, accounts(0), move_type.clone(), cell.clone())
+            .unwrap();
+
+        testing_env!(get_context_at(accounts(1), 3));
+        let hash = MoveCommitment::hash_move(&MoveType::SWAP, &None, "salt");
+        let game = contract.commit_move(index, hash).unwrap();
+        assert_eq!(game.commitment.unwrap().player, Player::Second);
+        testing_env!(get_context_at(accounts(0), 4));
+        contract.request_takeback(index).unwrap();
+        testing_env!(get_context_at(accounts(1), 5));
+        contract.accept_takeback(index).unwrap();
+        testing_env!(get_context_at(env::current_account_id(), 5));
+        contract.takeback_internal(index).unwrap();
+
+        let game = contract.get_game(index).unwrap();
+        assert_eq!(game.turn, 0);
+        assert!(game.commitment.is_none());
+        testing_env!(get_context_at(accounts(1), 6 + REVEAL_TIMEOUT));
+        assert_eq!(
+            contract.claim_reveal_timeout(index).err(),
+            Some(HexError::NoCommittedMove)
+        );
+        testing_env!(get_context_at(accounts(0), 6 + REVEAL_TIMEOUT));
+        assert!(contract.make_move(index, move_type, cell).is_ok());
+        assert!(contract.commit_move(index, hash).is_ok());
+    }
+}
@@ -0,0 +1,54 @@
+use hex_rules::Board;
+use near_sdk::{StorageUsage, Timestamp};
+
+use crate::utils::{MOVE_HISTORY_STORAGE, MOVE_RECORD_STORAGE};
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MoveRecord {
+    pub move_type: MoveType,
+    pub cell: Cell,
+    /// The rule the swap was made with, for `MoveType::SWAP` moves.
+    pub swap_rule: Option<SwapRule>,
+    pub block_height: BlockHeight,
+    pub timestamp: Timestamp,
+}
+
+impl MoveRecord {
+    /// Records a move made in the current block. For `MoveType::SWAP` the cell is
+    /// the one the swapped stone ends up on.
+    pub fn new(move_type: MoveType, cell: Cell) -> Self {
+        Self {
+            move_type,
+            cell,
+            swap_rule: None,
+            block_height: env::block_height(),
+            timestamp: env::block_timestamp(),
+        }
+    }
+}
+
+/// Storage reserved for the move history of a game when it's created, and
+/// the account that paid for it. What the history didn't use is refunded
+/// once the game is finished.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct HistoryDeposit {
+    pub payer: AccountId,
+    pub storage: StorageUsage,
+}
+
+/// Storage taken by a move history of the given length.
+pub fn history_storage(moves: u64) -> StorageUsage {
+    if moves == 0 {
+        0
+    } else {
+        MOVE_HISTORY_STORAGE + moves * MOVE_RECORD_STORAGE
+    }
+}
+
+/// Storage the move history of a game on the board can take at most: a
+/// stone on every cell and the swap.
+pub fn max_history_storage(board: &Board) -> StorageUsage {
+    history_storage((board.width * board.height) as u64 + 1)
+}
@@ -0,0 +1,46 @@
+use crate::*;
+
+/// Outcome of checking a move without making it. For an illegal move `error`
+/// and `message` hold the code and description `make_move` would fail with.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MoveVerdict {
+    pub is_legal: bool,
+    pub error: Option<String>,
+    pub message: Option<String>,
+}
+
+impl From<Result<(), HexError>> for MoveVerdict {
+    fn from(result: Result<(), HexError>) -> Self {
+        match result {
+            Ok(()) => Self {
+                is_legal: true,
+                error: None,
+                message: None,
+            },
+            Err(error) => Self {
+                is_legal: false,
+                error: Some(error.code().to_string()),
+                message: Some(error.message().to_string()),
+            },
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod move_verdict_tests {
+    use super::*;
+
+    #[test]
+    fn test_from_result() {
+        assert!(MoveVerdict::from(Ok(())).is_legal);
+        assert_eq!(
+            MoveVerdict::from(Err(HexError::NotYourTurn)),
+            MoveVerdict {
+                is_legal: false,
+                error: Some("ERR_NOT_YOUR_TURN".to_string()),
+                message: Some("It's not your turn.".to_string()),
+            }
+        );
+    }
+}
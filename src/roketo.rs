@@ -15,9 +15,9 @@ pub(crate) fn roketo_create_stream(
     game_playtime: u32,
     receiver_id: AccountId,
 ) -> Promise {
-    let tokens_per_sec = (bid + game_playtime as u128 - 1) / game_playtime as u128;
-    let fee = (bid + 9) / 10;
-    let msg = format!("{{\"Create\":{{\"request\":{{\"balance\":\"{}\", \"owner_id\":\"{}\",\"receiver_id\":\"{}\",\"token_name\": \"wrap.testnet\", \"tokens_per_sec\":\"{}\"}}}}}}", bid, env::current_account_id(), receiver_id.to_string(), tokens_per_sec);
+    let tokens_per_sec = bid.div_ceil(game_playtime as u128);
+    let fee = bid.div_ceil(10);
+    let msg = format!("{{\"Create\":{{\"request\":{{\"balance\":\"{}\", \"owner_id\":\"{}\",\"receiver_id\":\"{}\",\"token_name\": \"wrap.testnet\", \"tokens_per_sec\":\"{}\"}}}}}}", bid, env::current_account_id(), receiver_id, tokens_per_sec);
     ext_wrap::ext(WRAP_ACC.parse().unwrap())
         .with_attached_deposit(bid + fee)
         .near_deposit()
@@ -0,0 +1,539 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use hex_rules::{Board, BLOCKED};
+
+use crate::initial_position::InitialPosition;
+use crate::utils::MAX_FIELD_SIZE;
+use crate::*;
+
+/// Move values HexGui uses for the swap move with `SwapRule::SwapPieces`, where
+/// the stone is mirrored, and with `SwapRule::SwapSides`, where the players
+/// exchange colors.
+pub const SGF_SWAP: &str = "swap-pieces";
+pub const SGF_SWAP_SIDES: &str = "swap-sides";
+/// Ruleset (`RU`) of misère games, where the player who connects loses.
+pub const SGF_MISERE: &str = "misere";
+
+type SgfNode = Vec<(String, Vec<String>)>;
+
+fn cell_from_sgf(value: &str, board: &Board) -> Result<Cell, String> {
+    let cell: Cell = value.parse()?;
+    if !board.contains(&cell) {
+        return Err(format!("Move {} is out of bounds.", value));
+    }
+    Ok(cell)
+}
+
+/// Renders the game in the SGF format for Hex (`GM[11]`) as used by HexGui.
+/// The first player plays black, the second one plays white. Stones of the
+/// initial position are written as `AB` and `AW` setup properties, and holes
+/// as the custom `HO` property, as HexGui has no notion of them. Misère games
+/// are marked with `RU[misere]`, and games won by resignation or on time with
+/// `R` or `T` after the winner in `RE`, e.g. `RE[W+R]`.
+pub fn game_to_sgf(game: &Game, moves: &[MoveRecord]) -> String {
+    let size = if game.board.is_square() {
+        game.board.width.to_string()
+    } else {
+        format!("{}:{}", game.board.width, game.board.height)
+    };
+    let mut sgf = format!(
+        "(;FF[4]GM[11]AP[hex-game]SZ[{}]PB[{}]PW[{}]",
+        size, game.first_player, game.second_player
+    );
+    let how = match game.finish_reason {
+        Some(FinishReason::Resignation) => "R",
+        Some(FinishReason::Timeout | FinishReason::OutOfTime) => "T",
+        _ => "",
+    };
+    match game.winner {
+        Some(Player::First) => sgf.push_str(&format!("RE[B+{}]", how)),
+        Some(Player::Second) => sgf.push_str(&format!("RE[W+{}]", how)),
+        None => {}
+    }
+    if game.misere {
+        sgf.push_str(&format!("RU[{}]", SGF_MISERE));
+    }
+    let board = &game.board;
+    let holes: Vec<Cell> = board
+        .cells()
+        .filter(|cell| board.get_cell(cell) == BLOCKED)
+        .collect();
+    if !holes.is_empty() {
+        sgf.push_str("HO");
+        for cell in holes {
+            sgf.push_str(&format!("[{}]", cell));
+        }
+    }
+    if let Some(initial_position) = &game.initial_position {
+        for (ident, cells) in [
+            ("AB", &initial_position.red),
+            ("AW", &initial_position.blue),
+        ] {
+            if !cells.is_empty() {
+                sgf.push_str(ident);
+                for cell in cells {
+                    sgf.push_str(&format!("[{}]", cell));
+                }
+            }
+        }
+        if initial_position.blue_moves_first {
+            sgf.push_str("PL[W]");
+        }
+    }
+    let first_turn = game.turn - moves.len();
+    for (turn, record) in moves.iter().enumerate() {
+        let color = if (first_turn + turn).is_multiple_of(2) {
+            'B'
+        } else {
+            'W'
+        };
+        let value = match record.move_type {
+            MoveType::PLACE => record.cell.to_string(),
+            MoveType::SWAP if game.swap_rule == SwapRule::SwapSides => SGF_SWAP_SIDES.to_string(),
+            MoveType::SWAP => SGF_SWAP.to_string(),
+        };
+        sgf.push_str(&format!(";{}[{}]", color, value));
+    }
+    sgf.push(')');
+    sgf
+}
+
+/// Replays the main line of an SGF game record. Players default to `first`
+/// and `second` if the record doesn't name them. Cells may be written in both
+/// notations `Cell` can be parsed from.
+pub fn game_from_sgf(sgf: &str) -> Result<GameWithData, String> {
+    let nodes = parse_sgf(sgf)?;
+    let root = nodes.first().ok_or("SGF doesn't contain any nodes.")?;
+    let property = |name: &str| {
+        root.iter()
+            .find(|(ident, _)| ident == name)
+            .and_then(|(_, values)| values.first())
+            .map(|value| value.as_str())
+    };
+
+    if let Some(game_type) = property("GM") {
+        if game_type != "11" {
+            return Err(format!("Unsupported game type {}.", game_type));
+        }
+    }
+    let (width, height) = match property("SZ") {
+        Some(size) => {
+            let mut dimensions = size.splitn(2, ':').map(str::parse::<usize>);
+            match (dimensions.next(), dimensions.next()) {
+                (Some(Ok(width)), None) => (width, width),
+                (Some(Ok(width)), Some(Ok(height))) => (width, height),
+                _ => return Err(format!("Unsupported board size {}.", size)),
+            }
+        }
+        None => (11, 11),
+    };
+    if [width, height]
+        .iter()
+        .any(|&size| size == 0 || size > MAX_FIELD_SIZE)
+    {
+        return Err(format!("Unsupported board size {}:{}.", width, height));
+    }
+    let player = |name: &str, default: &str| -> Result<AccountId, String> {
+        property(name)
+            .unwrap_or(default)
+            .parse()
+            .map_err(|_| format!("Invalid player {}.", property(name).unwrap()))
+    };
+
+    let mut game_with_data = GameWithData::new(
+        player("PB", "first")?,
+        player("PW", "second")?,
+        width,
+        height,
+        None,
+        None,
+    );
+    let setup = |name: &str| -> Result<Vec<Cell>, String> {
+        root.iter()
+            .filter(|(ident, _)| ident == name)
+            .flat_map(|(_, values)| values)
+            .map(|value| cell_from_sgf(value, &game_with_data.game.board))
+            .collect()
+    };
+    let holes = setup("HO")?;
+    let initial_position = InitialPosition {
+        red: setup("AB")?,
+        blue: setup("AW")?,
+        blue_moves_first: property("PL") == Some("W"),
+    };
+    match property("RU") {
+        Some(SGF_MISERE) => game_with_data.game.misere = true,
+        Some(ruleset) => return Err(format!("Unsupported ruleset {}.", ruleset)),
+        None => {}
+    }
+    game_with_data
+        .add_holes(&holes)
+        .map_err(|error| error.to_string())?;
+    if initial_position != InitialPosition::default() {
+        game_with_data.game.swap_rule = SwapRule::NoSwap;
+        game_with_data
+            .set_initial_position(initial_position)
+            .map_err(|error| error.to_string())?;
+    }
+    game_with_data.game.start();
+
+    for (ident, values) in nodes.iter().flatten() {
+        let is_black = match ident.as_str() {
+            "B" => true,
+            "W" => false,
+            _ => continue,
+        };
+        let game = &mut game_with_data.game;
+        if game.is_finished {
+            return Err("Move after the end of the game.".to_string());
+        }
+        if is_black != game.turn.is_multiple_of(2) {
+            return Err(format!("Unexpected {} move on turn {}.", ident, game.turn));
+        }
+        let value = values.first().map(|value| value.as_str()).unwrap_or("");
+        match value {
+            SGF_SWAP | SGF_SWAP_SIDES | "swap" => {
+                match value {
+                    SGF_SWAP => game.swap_rule = SwapRule::SwapPieces,
+                    SGF_SWAP_SIDES => game.swap_rule = SwapRule::SwapSides,
+                    _ => {}
+                }
+                game_with_data
+                    .apply_move(MoveType::SWAP, None)
+                    .map_err(|error| format!("Move {}: {}", value, error))?;
+            }
+            "resign" => {
+                let winner = if is_black {
+                    Player::Second
+                } else {
+                    Player::First
+                };
+                game.finish(Some(winner), FinishReason::Resignation);
+            }
+            _ => {
+                let cell = cell_from_sgf(value, &game.board)?;
+                game_with_data
+                    .apply_move(MoveType::PLACE, Some(cell))
+                    .map_err(|error| format!("Move {}: {}", value, error))?;
+            }
+        }
+    }
+
+    let game = &mut game_with_data.game;
+    let result = property("RE").unwrap_or("");
+    let winner = match result.get(..2) {
+        Some("B+") => Some(Player::First),
+        Some("W+") => Some(Player::Second),
+        _ => None,
+    };
+    if let (false, Some(winner)) = (game.is_finished, winner) {
+        game.is_finished = true;
+        game.winner = Some(winner);
+        // The reason stays unknown for results like `B+` or `B+3.5`.
+        game.finish_reason = match &result[2..] {
+            "R" | "Resign" => Some(FinishReason::Resignation),
+            "T" | "Time" => Some(FinishReason::Timeout),
+            _ => None,
+        };
+    }
+    Ok(game_with_data)
+}
+
+/// Parses the main line of the first game tree in the collection.
+fn parse_sgf(sgf: &str) -> Result<Vec<SgfNode>, String> {
+    let mut chars = sgf.chars().peekable();
+    skip_whitespace(&mut chars);
+    if chars.next() != Some('(') {
+        return Err("SGF must start with a game tree.".to_string());
+    }
+    parse_game_tree(&mut chars)
+}
+
+fn parse_game_tree(chars: &mut Peekable<Chars>) -> Result<Vec<SgfNode>, String> {
+    let mut nodes = Vec::new();
+    let mut has_variation = false;
+    loop {
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(';') if !has_variation => nodes.push(parse_node(chars)?),
+            Some('(') => {
+                let variation = parse_game_tree(chars)?;
+                if !has_variation {
+                    nodes.extend(variation);
+                    has_variation = true;
+                }
+            }
+            Some(')') => return Ok(nodes),
+            _ => return Err("Malformed SGF game tree.".to_string()),
+        }
+    }
+}
+
+fn parse_node(chars: &mut Peekable<Chars>) -> Result<SgfNode, String> {
+    let mut node = Vec::new();
+    loop {
+        skip_whitespace(chars);
+        let mut ident = String::new();
+        while let Some(&c) = chars.peek() {
+            if !c.is_ascii_uppercase() {
+                break;
+            }
+            ident.push(c);
+            chars.next();
+        }
+        if ident.is_empty() {
+            return Ok(node);
+        }
+
+        let mut values = Vec::new();
+        skip_whitespace(chars);
+        while chars.peek() == Some(&'[') {
+            chars.next();
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some('\\') => value.extend(chars.next()),
+                    Some(']') => break,
+                    Some(c) => value.push(c),
+                    None => return Err("Unterminated SGF property value.".to_string()),
+                }
+            }
+            values.push(value);
+            skip_whitespace(chars);
+        }
+        if values.is_empty() {
+            return Err(format!("SGF property {} has no values.", ident));
+        }
+        node.push((ident, values));
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Games of Y have no SGF format HexGui could read, so only Hex games
+    /// are exported.
+    pub fn get_game_sgf(&self, index: GameIndex) -> Option<String> {
+        let game = self.games.get(&index)?.game;
+        if game.board.topology != Topology::Hex {
+            return None;
+        }
+        let moves = self.get_move_history(index, None, Some(u64::MAX));
+        Some(game_to_sgf(&game, &moves))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod sgf_tests {
+    use near_sdk::test_utils::accounts;
+
+    use super::*;
+
+    fn record(move_type: MoveType, x: usize, y: usize) -> MoveRecord {
+        MoveRecord::new(move_type, Cell::new(x, y))
+    }
+
+    #[test]
+    fn test_game_to_sgf() {
+        let mut game_with_data = GameWithData::new(accounts(0), accounts(1), 2, 2, None, None);
+        game_with_data
+            .apply_move(MoveType::PLACE, Some(Cell::new(1, 0)))
+            .unwrap();
+        game_with_data.apply_move(MoveType::SWAP, None).unwrap();
+        game_with_data
+            .apply_move(MoveType::PLACE, Some(Cell::new(1, 0)))
+            .unwrap();
+        game_with_data
+            .apply_move(MoveType::PLACE, Some(Cell::new(1, 1)))
+            .unwrap();
+        let moves = vec![
+            record(MoveType::PLACE, 1, 0),
+            record(MoveType::SWAP, 0, 1),
+            record(MoveType::PLACE, 1, 0),
+            record(MoveType::PLACE, 1, 1),
+        ];
+
+        assert_eq!(
+            game_to_sgf(&game_with_data.game, &moves),
+            "(;FF[4]GM[11]AP[hex-game]SZ[2]PB[alice]PW[bob]RE[W+];B[b1];W[swap-pieces];B[b1];W[b2])"
+        );
+    }
+
+    #[test]
+    fn test_game_from_sgf() {
+        let game_with_data = game_from_sgf(
+            "(;FF[4]GM[11]SZ[2]PB[alice]PW[bob]\n;B[b1];W[swap-pieces]\n;B[b1];W[b2])",
+        )
+        .unwrap();
+        let game = game_with_data.game;
+        assert_eq!(game.first_player, accounts(0));
+        assert_eq!(game.second_player, accounts(1));
+        assert_eq!(game.turn, 4);
+        assert_eq!(game.board.get_cell(&Cell::new(1, 0)), 1);
+        assert_eq!(game.board.get_cell(&Cell::new(0, 1)), 2);
+        assert_eq!(game.board.get_cell(&Cell::new(1, 1)), 2);
+        assert!(game.is_finished);
+        assert!(matches!(game.winner, Some(Player::Second)));
+    }
+
+    #[test]
+    fn test_game_from_sgf_variations_and_result() {
+        let game = game_from_sgf("(;GM[11]SZ[5]RE[B+Resign];B[c3](;W[c2];B[b4])(;W[a1]))")
+            .unwrap()
+            .game;
+        assert_eq!(game.first_player, "first".parse::<AccountId>().unwrap());
+        assert_eq!(game.turn, 3);
+        assert_eq!(game.board.get_cell(&Cell::new(2, 1)), 2);
+        assert_eq!(game.board.get_cell(&Cell::new(0, 0)), 0);
+        assert!(game.is_finished);
+        assert!(matches!(game.winner, Some(Player::First)));
+        assert_eq!(game.finish_reason, Some(FinishReason::Resignation));
+    }
+
+    #[test]
+    fn test_resignation_and_timeout_sgf() {
+        for (winner, reason, result) in [
+            (Player::Second, FinishReason::Resignation, "W+R"),
+            (Player::First, FinishReason::Timeout, "B+T"),
+            (Player::First, FinishReason::OutOfTime, "B+T"),
+        ] {
+            let mut game_with_data = GameWithData::new(accounts(0), accounts(1), 3, 3, None, None);
+            game_with_data
+                .apply_move(MoveType::PLACE, Some(Cell::new(0, 0)))
+                .unwrap();
+            game_with_data.game.finish(Some(winner.clone()), reason);
+            let sgf = game_to_sgf(&game_with_data.game, &[record(MoveType::PLACE, 0, 0)]);
+            assert_eq!(
+                sgf,
+                format!(
+                    "(;FF[4]GM[11]AP[hex-game]SZ[3]PB[alice]PW[bob]RE[{}];B[a1])",
+                    result
+                )
+            );
+
+            let game = game_from_sgf(&sgf).unwrap().game;
+            assert!(game.is_finished);
+            assert_eq!(game.winner, Some(winner));
+            let reason = if result.ends_with('R') {
+                FinishReason::Resignation
+            } else {
+                FinishReason::Timeout
+            };
+            assert_eq!(game.finish_reason, Some(reason));
+        }
+        let game = game_from_sgf("(;GM[11]SZ[3]RE[W+];B[a1])").unwrap().game;
+        assert!(matches!(game.winner, Some(Player::Second)));
+        assert_eq!(game.finish_reason, None);
+    }
+
+    #[test]
+    fn test_rectangular_game_sgf() {
+        let sgf = "(;FF[4]GM[11]AP[hex-game]SZ[3:2]PB[alice]PW[bob]RE[W+];B[a1];W[swap-sides];B[c1];W[a2])";
+        let game_with_data = game_from_sgf(sgf).unwrap();
+        assert_eq!(game_with_data.game.board.width, 3);
+        assert_eq!(game_with_data.game.board.height, 2);
+        assert!(game_with_data.game.sides_swapped);
+        assert!(matches!(game_with_data.game.winner, Some(Player::Second)));
+
+        let moves = vec![
+            record(MoveType::PLACE, 0, 0),
+            record(MoveType::SWAP, 0, 0),
+            record(MoveType::PLACE, 2, 0),
+            record(MoveType::PLACE, 0, 1),
+        ];
+        assert_eq!(game_to_sgf(&game_with_data.game, &moves), sgf);
+    }
+
+    #[test]
+    fn test_handicap_game_sgf() {
+        let sgf =
+            "(;FF[4]GM[11]AP[hex-game]SZ[3]PB[alice]PW[bob]AB[b2]AW[a1][c3]PL[W];W[a2];B[b1])";
+        let game_with_data = game_from_sgf(sgf).unwrap();
+        let game = &game_with_data.game;
+        assert_eq!(
+            game.initial_position,
+            Some(InitialPosition {
+                red: vec![Cell::new(1, 1)],
+                blue: vec![Cell::new(0, 0), Cell::new(2, 2)],
+                blue_moves_first: true,
+            })
+        );
+        assert_eq!(game.turn, 3);
+        assert_eq!(game.board.get_cell(&Cell::new(0, 1)), 2);
+        assert_eq!(game.board.get_cell(&Cell::new(1, 0)), 1);
+
+        let moves = vec![record(MoveType::PLACE, 0, 1), record(MoveType::PLACE, 1, 0)];
+        assert_eq!(game_to_sgf(game, &moves), sgf);
+        assert!(game_from_sgf("(;GM[11]SZ[3]AB[a1][b2]AW[b2])").is_err());
+    }
+
+    #[test]
+    fn test_holes_sgf() {
+        let mut game_with_data = GameWithData::new(accounts(0), accounts(1), 3, 3, None, None);
+        game_with_data
+            .add_holes(&[Cell::new(1, 1), Cell::new(0, 2)])
+            .unwrap();
+        game_with_data.game.start();
+        game_with_data
+            .apply_move(MoveType::PLACE, Some(Cell::new(0, 0)))
+            .unwrap();
+        let moves = vec![record(MoveType::PLACE, 0, 0)];
+        let sgf = game_to_sgf(&game_with_data.game, &moves);
+        assert_eq!(
+            sgf,
+            "(;FF[4]GM[11]AP[hex-game]SZ[3]PB[alice]PW[bob]HO[b2][a3];B[a1])"
+        );
+
+        let imported = game_from_sgf(&sgf).unwrap();
+        assert_eq!(imported.game.board, game_with_data.game.board);
+        assert_eq!(game_to_sgf(&imported.game, &moves), sgf);
+        assert!(game_from_sgf("(;GM[11]SZ[3]HO[b2];B[b2])").is_err());
+    }
+
+    #[test]
+    fn test_misere_sgf() {
+        let mut game_with_data = GameWithData::new(accounts(0), accounts(1), 2, 2, None, None);
+        game_with_data.game.misere = true;
+        game_with_data.game.start();
+        for (x, y) in [(0, 0), (1, 0), (0, 1)] {
+            game_with_data
+                .apply_move(MoveType::PLACE, Some(Cell::new(x, y)))
+                .unwrap();
+        }
+        let moves = vec![
+            record(MoveType::PLACE, 0, 0),
+            record(MoveType::PLACE, 1, 0),
+            record(MoveType::PLACE, 0, 1),
+        ];
+        let sgf = game_to_sgf(&game_with_data.game, &moves);
+        assert_eq!(
+            sgf,
+            "(;FF[4]GM[11]AP[hex-game]SZ[2]PB[alice]PW[bob]RE[W+]RU[misere];B[a1];W[b1];B[a2])"
+        );
+
+        let imported = game_from_sgf(&sgf).unwrap().game;
+        assert!(imported.misere);
+        assert!(matches!(imported.winner, Some(Player::Second)));
+        assert_eq!(game_to_sgf(&imported, &moves), sgf);
+        assert!(game_from_sgf("(;GM[11]SZ[2]RU[Japanese])").is_err());
+    }
+
+    #[test]
+    fn test_game_from_sgf_errors() {
+        assert!(game_from_sgf("(;GM[1]SZ[19])").is_err());
+        assert!(game_from_sgf("(;GM[11]SZ[27])").is_err());
+        assert!(game_from_sgf("(;GM[11]SZ[5:0])").is_err());
+        assert!(game_from_sgf("(;GM[11]SZ[5:3];B[a4])").is_err());
+        assert!(game_from_sgf("(;GM[11]SZ[5];B[f1])").is_err());
+        assert!(game_from_sgf("(;GM[11]SZ[5];W[a1])").is_err());
+        assert!(game_from_sgf("(;GM[11]SZ[5];B[a1];W[a1])").is_err());
+        assert!(game_from_sgf("(;GM[11]SZ[5];B[a1];W[b1];B[swap])").is_err());
+        assert!(game_from_sgf("(;GM[11]SZ[5];B[a1]").is_err());
+    }
+}
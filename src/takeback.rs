@@ -0,0 +1,229 @@
+use crate::errors::{ensure, HexError};
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Asks the opponent to revert the last move, which must be made by the
+    /// predecessor. The request is dropped once the opponent moves.
+    #[handle_result]
+    pub fn request_takeback(&mut self, index: GameIndex) -> Result<Game, HexError> {
+        let mut game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
+        let game = &mut game_with_data.game;
+        ensure(!game.is_finished, HexError::GameFinished)?;
+        ensure(game.is_started, HexError::GameNotStarted)?;
+        ensure(game.allow_takebacks, HexError::TakebacksDisabled)?;
+        // Games from an initial position start at a later turn, so count
+        // the moves actually made instead.
+        ensure(
+            self.moves
+                .get(&index)
+                .is_some_and(|moves| !moves.is_empty()),
+            HexError::NoMoveToTakeBack,
+        )?;
+        ensure(
+            game.takeback_requested_by.is_none(),
+            HexError::TakebackAlreadyRequested,
+        )?;
+        let player = if game.turn % 2 == 1 {
+            Player::First
+        } else {
+            Player::Second
+        };
+        let account_id = env::predecessor_account_id();
+        ensure(
+            &account_id == game.player_account(&player),
+            HexError::NotYourMove,
+        )?;
+
+        game.takeback_requested_by = Some(player);
+        self.games.insert(&index, &game_with_data);
+        let game = game_with_data.game;
+        env::log_str(&format!("{} requested a takeback.", account_id));
+        HexEvent::TakebackRequested {
+            game_id: index,
+            player: account_id,
+            turn: game.turn - 1,
+        }
+        .emit();
+        Ok(game)
+    }
+
+    #[handle_result]
+    pub fn accept_takeback(&mut self, index: GameIndex) -> Result<Promise, HexError> {
+        let game_with_data = self.games.get(&index).ok_or(HexError::GameNotFound)?;
+        let game = game_with_data.game;
+        ensure(!game.is_finished, HexError::GameFinished)?;
+        ensure(
+            game.takeback_requested_by.is_some(),
+            HexError::TakebackNotRequested,
+        )?;
+        ensure(
+            &env::predecessor_account_id() == game.player_to_move(),
+            HexError::NotOpponent,
+        )?;
+
+        Ok(if let Some(promise) = self.check_stream_bids(index) {
+            promise.then(
+                Self::ext(env::current_account_id()).resolve_streams(index, GameAction::Takeback),
+            )
+        } else {
+            Self::ext(env::current_account_id()).takeback_internal(index)
+        })
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod takeback_tests {
+    use near_sdk::{test_utils::accounts, testing_env, ONE_NEAR};
+
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_takeback() {
+        let mut contract = Contract::new();
+        let index = create_default_game(&mut contract, with_size(5));
+        place_stone(&mut contract, index, accounts(0), Cell::new(2, 2));
+        place_stone(&mut contract, index, accounts(1), Cell::new(1, 1));
+
+        testing_env!(get_context(accounts(1)));
+        contract.request_takeback(index).unwrap();
+        testing_env!(get_context(accounts(0)));
+        contract.accept_takeback(index).unwrap();
+        testing_env!(get_context(env::current_account_id()));
+        contract.takeback_internal(index).unwrap();
+
+        let game = contract.get_game(index).unwrap();
+        assert_eq!(game.turn, 1);
+        assert_eq!(game.board.get_cell(&Cell::new(1, 1)), 0);
+        assert_eq!(game.board.get_cell(&Cell::new(2, 2)), 1);
+        assert!(game.takeback_requested_by.is_none());
+        assert_eq!(contract.get_move_history(index, None, None).len(), 1);
+
+        place_stone(&mut contract, index, accounts(1), Cell::new(3, 3));
+        assert_eq!(contract.get_game(index).unwrap().turn, 2);
+    }
+
+    #[test]
+    fn test_takeback_dropped_after_move() {
+        let mut contract = Contract::new();
+        let index = create_default_game(&mut contract, with_size(5));
+        place_stone(&mut contract, index, accounts(0), Cell::new(2, 2));
+
+        testing_env!(get_context(accounts(0)));
+        contract.request_takeback(index).unwrap();
+        place_stone(&mut contract, index, accounts(1), Cell::new(1, 1));
+        assert!(contract
+            .get_game(index)
+            .unwrap()
+            .takeback_requested_by
+            .is_none());
+    }
+
+    #[test]
+    fn test_takeback_from_initial_position() {
+        let mut contract = Contract::new();
+        let options = GameOptions {
+            swap_rule: Some(SwapRule::NoSwap),
+            ..with_size(5)
+        };
+        let index = create_default_game(&mut contract, options);
+        let mut game_with_data = contract.games.get(&index).unwrap();
+        game_with_data
+            .set_initial_position(InitialPosition {
+                red: vec![Cell::new(0, 0)],
+                blue: vec![Cell::new(4, 4)],
+                blue_moves_first: true,
+            })
+            .unwrap();
+        contract.games.insert(&index, &game_with_data);
+
+        testing_env!(get_context(accounts(0)));
+        assert_eq!(
+            contract.request_takeback(index).err(),
+            Some(HexError::NoMoveToTakeBack)
+        );
+        place_stone(&mut contract, index, accounts(1), Cell::new(2, 2));
+        testing_env!(get_context(accounts(1)));
+        contract.request_takeback(index).unwrap();
+        testing_env!(get_context(accounts(0)));
+        contract.accept_takeback(index).unwrap();
+        testing_env!(get_context(env::current_account_id()));
+        contract.takeback_internal(index).unwrap();
+        assert_eq!(contract.get_game(index).unwrap().turn, 1);
+    }
+
+    #[test]
+    fn test_takeback_of_opponent_move() {
+        let mut contract = Contract::new();
+        let index = create_default_game(&mut contract, with_size(5));
+        place_stone(&mut contract, index, accounts(0), Cell::new(2, 2));
+
+        testing_env!(get_context(accounts(1)));
+        assert_eq!(
+            contract.request_takeback(index).err(),
+            Some(HexError::NotYourMove)
+        );
+    }
+
+    #[test]
+    fn test_accept_own_takeback() {
+        let mut contract = Contract::new();
+        let index = create_default_game(&mut contract, with_size(5));
+        place_stone(&mut contract, index, accounts(0), Cell::new(2, 2));
+
+        testing_env!(get_context(accounts(0)));
+        contract.request_takeback(index).unwrap();
+        assert_eq!(
+            contract.accept_takeback(index).err(),
+            Some(HexError::NotOpponent)
+        );
+    }
+
+    #[test]
+    fn test_takeback_disabled() {
+        let mut contract = Contract::new();
+        let index = create_default_game(
+            &mut contract,
+            GameOptions {
+                field_size: Some(5),
+                allow_takebacks: Some(false),
+                ..Default::default()
+            },
+        );
+        place_stone(&mut contract, index, accounts(0), Cell::new(2, 2));
+
+        testing_env!(get_context(accounts(0)));
+        assert_eq!(
+            contract.request_takeback(index).err(),
+            Some(HexError::TakebacksDisabled)
+        );
+    }
+
+    #[test]
+    fn test_takebacks_in_wagered_games() {
+        let bid = Some(U128(5 * ONE_NEAR));
+        assert!(
+            GameSettings::new(GameOptions::default())
+                .unwrap()
+                .allow_takebacks
+        );
+        assert!(
+            !GameSettings::new(GameOptions {
+                bid,
+                ..Default::default()
+            })
+            .unwrap()
+            .allow_takebacks
+        );
+        assert!(
+            GameSettings::new(GameOptions {
+                bid,
+                allow_takebacks: Some(true),
+                ..Default::default()
+            })
+            .unwrap()
+            .allow_takebacks
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Fixtures shared by the contract tests. Games are played by `accounts(0)`
+//! as the first player and `accounts(1)` as the second one.
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{testing_env, VMContext, ONE_NEAR};
+
+use crate::*;
+
+pub(crate) fn get_context(account: AccountId) -> VMContext {
+    get_context_at(account, 0)
+}
+
+/// Context of a call by `account` at the given block height. The attached
+/// deposit covers the storage of a new game.
+pub(crate) fn get_context_at(account: AccountId, block_height: BlockHeight) -> VMContext {
+    VMContextBuilder::new()
+        .predecessor_account_id(account)
+        .attached_deposit(ONE_NEAR)
+        .block_index(block_height)
+        .build()
+}
+
+/// Options of a game on a board of the given size.
+pub(crate) fn with_size(field_size: usize) -> GameOptions {
+    GameOptions {
+        field_size: Some(field_size),
+        ..Default::default()
+    }
+}
+
+/// Creates a game with the given options, which waits for `accounts(1)` to
+/// accept it.
+pub(crate) fn create_pending_game(contract: &mut Contract, options: GameOptions) -> GameIndex {
+    testing_env!(get_context(accounts(0)));
+    contract
+        .create_game(accounts(0), accounts(1), Some(options), None, None)
+        .unwrap()
+}
+
+/// Creates a game with the given options and accepts it, so that it can be
+/// played. Bets of games with a bid still have to be placed.
+pub(crate) fn create_default_game(contract: &mut Contract, options: GameOptions) -> GameIndex {
+    let index = create_pending_game(contract, options);
+    accept(contract, index, accounts(1));
+    index
+}
+
+pub(crate) fn accept(contract: &mut Contract, index: GameIndex, account: AccountId) {
+    testing_env!(get_context(account));
+    contract.accept_game(index).unwrap();
+}
+
+/// Marks the bets of both players as placed, as if their streams were created.
+pub(crate) fn place_bets(contract: &mut Contract, index: GameIndex) {
+    let mut bid = contract.bids.get(&index).unwrap();
+    bid.did_first_player_bet = true;
+    bid.did_second_player_bet = true;
+    contract.bids.insert(&index, &bid);
+}
+
+/// Runs `make_move` checks as `account` and then applies the move the way
+/// the `make_move_internal` callback does.
+pub(crate) fn apply_move(
+    contract: &mut Contract,
+    index: GameIndex,
+    account: AccountId,
+    move_type: MoveType,
+    cell: Option<Cell>,
+) {
+    testing_env!(get_context(account.clone()));
+    contract
+        .make_move(index, move_type.clone(), cell.clone())
+        .unwrap();
+    testing_env!(get_context(env::current_account_id()));
+    contract
+        .make_move_internal(index, account, move_type, cell)
+        .unwrap();
+}
+
+pub(crate) fn place_stone(
+    contract: &mut Contract,
+    index: GameIndex,
+    account: AccountId,
+    cell: Cell,
+) {
+    apply_move(contract, index, account, MoveType::PLACE, Some(cell));
+}
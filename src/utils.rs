@@ -1,9 +1,37 @@
-use near_sdk::{Balance, Gas, ONE_NEAR};
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::{
+    env, serde_json, AccountId, Balance, BlockHeight, Gas, Promise, PromiseResult, StorageUsage,
+    ONE_NEAR,
+};
+
+use crate::errors::HexError;
+
+pub use hex_rules::MAX_FIELD_SIZE;
+
+pub const DEFAULT_FIELD_SIZE: usize = 11;
+/// Bigger boards are logged as the list of changed cells instead of a whole
+/// board after every move.
+pub const MAX_LOGGED_FIELD_SIZE: usize = 19;
 
 pub const MIN_PLAYTIME: u32 = 5 * 60;
 pub const MAX_PLAYTIME: u32 = 60 * 60;
 pub const DEFAULT_PLAYTIME: u32 = 20 * 60;
 
+pub const MIN_MOVE_TIMEOUT: BlockHeight = 5 * 60;
+pub const MAX_MOVE_TIMEOUT: BlockHeight = 7 * 24 * 60 * 60;
+pub const DEFAULT_MOVE_TIMEOUT: BlockHeight = 24 * 60 * 60;
+/// Blocks a player has to reveal a committed move in, after which the
+/// opponent can claim the win.
+pub const REVEAL_TIMEOUT: BlockHeight = 5 * 60;
+
+pub const DEFAULT_PAGE_SIZE: u64 = 50;
+
+/// Storage taken by the move history of a game besides its records.
+pub const MOVE_HISTORY_STORAGE: StorageUsage = 70;
+/// Storage taken by one record of the move history, with its key and the
+/// per-record overhead.
+pub const MOVE_RECORD_STORAGE: StorageUsage = 92;
+
 pub const MIN_BID: Balance = 2 * ONE_NEAR;
 pub const MAX_BID: Balance = 100 * ONE_NEAR;
 
@@ -17,3 +45,23 @@ pub const ROKETO_ACC: &str = "streaming-r-v2.dcversus.testnet";
 pub const WRAP_ACC: &str = "wrap.testnet";
 // pub const ROKETO_ACC: &str = "streaming.r-v2.near";
 // pub const WRAP_ACC: &str = "wrap.near";
+
+/// Returns the deposit for the released storage to the account that paid
+/// for it.
+pub fn refund_storage(account_id: AccountId, storage_released: StorageUsage) {
+    let refund = env::storage_byte_cost() * Balance::from(storage_released);
+    if refund > 0 {
+        Promise::new(account_id).transfer(refund);
+    }
+}
+
+/// Parses the JSON value returned by the promise with the given index.
+pub fn promise_result_json<T: DeserializeOwned>(index: u64) -> Result<T, HexError> {
+    match env::promise_result(index) {
+        PromiseResult::NotReady => unreachable!(),
+        PromiseResult::Successful(value) => {
+            serde_json::from_slice(&value).map_err(|_| HexError::WrongValueReceived)
+        }
+        PromiseResult::Failed => Err(HexError::CallFailed),
+    }
+}